@@ -11,7 +11,7 @@ cat << EOF
       (write-line "[31mFail[0m: " module ":" f)
       (write-line "  Expected: " b)
       (write-line "  Actual: " a)
-      (exit)
+      (exit 1)
     ))))
 EOF
 
@@ -0,0 +1,116 @@
+use crate::invalid_arguments;
+use crate::node::Node;
+
+//- (test "read-bytes" (let ((f (temp-file))) (begin (write-bytes f (list->bytes (quote (0 159 255)))) (let ((result (bytes->list (read-bytes f)))) (begin (delete-file f) result)))) (quote (0 159 255)))
+pub fn fn_read_bytes(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(filename)] => std::fs::read(filename)
+            .map(Node::Bytes)
+            .map_err(|e| format!("Failed to read file: {filename}: {e}")),
+        _ => invalid_arguments!("read-bytes", arguments, ["[Text(filename)]"]),
+    }
+}
+
+pub fn fn_write_bytes(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(filename), Node::Bytes(bytes)] => {
+            std::fs::write(filename, bytes)
+                .map_err(|e| format!("Failed to write file: {filename}: {e}"))?;
+            Ok(Node::Bool(true))
+        }
+        _ => invalid_arguments!("write-bytes", arguments, ["[Text(filename), Bytes(bytes)]"]),
+    }
+}
+
+//- (test "bytes->list" (bytes->list (string->bytes "AB")) (quote (65 66)))
+//- (test "bytes->list" (bytes->list (string->bytes "")) (quote ()))
+pub fn fn_bytes_to_list(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Bytes(bytes)] => Ok(Node::List(
+            bytes.iter().map(|b| Node::Number(i64::from(*b))).collect(),
+        )),
+        _ => invalid_arguments!("bytes->list", arguments, ["[Bytes(bytes)]"]),
+    }
+}
+
+//- (test "list->bytes" (list->bytes (quote (104 105))) (string->bytes "hi"))
+//- (test "list->bytes" (list->bytes (quote ())) (string->bytes ""))
+pub fn fn_list_to_bytes(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::List(list)] => list
+            .iter()
+            .map(|item| match item {
+                Node::Number(n) => {
+                    u8::try_from(*n).map_err(|_| format!("Byte value out of range: {n}"))
+                }
+                _ => Err(format!("Expected a list of byte values, found {item:?}")),
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Node::Bytes),
+        _ => invalid_arguments!("list->bytes", arguments, ["[List(numbers)]"]),
+    }
+}
+
+//- (test "bytes-slice" (bytes-slice (string->bytes "foobar") 0 3) (string->bytes "foo"))
+//- (test "bytes-slice" (bytes-slice (string->bytes "foobar") 3 3) (string->bytes ""))
+pub fn fn_bytes_slice(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Bytes(bytes), Node::Number(start), Node::Number(end)] => {
+            let start =
+                usize::try_from(*start).map_err(|_| format!("Invalid start index: {start}"))?;
+            let end = usize::try_from(*end).map_err(|_| format!("Invalid end index: {end}"))?;
+            if start <= end && end <= bytes.len() {
+                Ok(Node::Bytes(bytes[start..end].to_vec()))
+            } else {
+                Err(format!(
+                    "Invalid slice range: {start}..{end} for bytes of length {}",
+                    bytes.len()
+                ))
+            }
+        }
+        _ => invalid_arguments!(
+            "bytes-slice",
+            arguments,
+            ["[Bytes(bytes), Number(start), Number(end)]"]
+        ),
+    }
+}
+
+fn decode(encoding: &str, bytes: &[u8]) -> Result<String, String> {
+    match encoding {
+        "utf-8" | "utf8" => String::from_utf8(bytes.to_vec())
+            .map_err(|e| format!("Invalid UTF-8 at byte {}", e.utf8_error().valid_up_to())),
+        "utf-8-lossy" | "lossy" => Ok(String::from_utf8_lossy(bytes).to_string()),
+        "latin-1" | "iso-8859-1" => Ok(bytes.iter().map(|b| char::from(*b)).collect()),
+        "ascii" => match bytes.iter().position(|b| !b.is_ascii()) {
+            Some(index) => Err(format!("Invalid ASCII at byte {index}")),
+            None => Ok(bytes.iter().map(|b| char::from(*b)).collect()),
+        },
+        _ => Err(format!(
+            "Unknown encoding: {encoding} (expected utf-8, utf-8-lossy, latin-1 or ascii)"
+        )),
+    }
+}
+
+//- (test "bytes->string" (bytes->string (string->bytes "héllo")) "héllo")
+//- (test "bytes->string" (bytes->string "utf-8-lossy" (list->bytes (quote (104 255)))) "h�")
+//- (test "bytes->string" (bytes->string "latin-1" (list->bytes (quote (104 233)))) "hé")
+pub fn fn_bytes_to_string(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Bytes(bytes)] => decode("utf-8", bytes).map(Node::Text),
+        [Node::Text(encoding), Node::Bytes(bytes)] => decode(encoding, bytes).map(Node::Text),
+        _ => invalid_arguments!(
+            "bytes->string",
+            arguments,
+            ["[Bytes(bytes)]", "[Text(encoding), Bytes(bytes)]"]
+        ),
+    }
+}
+
+//- (test "string->bytes" (bytes->list (string->bytes "é")) (quote (195 169)))
+pub fn fn_string_to_bytes(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(text)] => Ok(Node::Bytes(text.as_bytes().to_vec())),
+        _ => invalid_arguments!("string->bytes", arguments, ["[Text(text)]"]),
+    }
+}
@@ -0,0 +1,109 @@
+use crate::invalid_arguments;
+use crate::node::Node;
+
+// Names accepted after `#\` in addition to single characters and `#\xHH`
+const CHARACTER_NAMES: [(&str, char); 9] = [
+    ("space", ' '),
+    ("newline", '\n'),
+    ("tab", '\t'),
+    ("return", '\r'),
+    ("nul", '\0'),
+    ("escape", '\u{1b}'),
+    ("delete", '\u{7f}'),
+    ("backspace", '\u{8}'),
+    ("alarm", '\u{7}'),
+];
+
+/// Resolve the text following `#\` to a character.
+pub fn from_name(name: &str) -> Result<char, String> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Ok(c);
+    }
+    if let Some(c) = CHARACTER_NAMES
+        .iter()
+        .find(|(candidate, _)| *candidate == name)
+        .map(|(_, c)| *c)
+    {
+        return Ok(c);
+    }
+    name.strip_prefix('x')
+        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+        .and_then(char::from_u32)
+        .ok_or_else(|| format!("Unknown character name: #\\{name}"))
+}
+
+/// The reader syntax for a character, e.g. `#\a` or `#\newline`.
+#[must_use]
+pub fn to_name(c: char) -> String {
+    match CHARACTER_NAMES
+        .iter()
+        .find(|(_, candidate)| *candidate == c)
+    {
+        Some((name, _)) => format!("#\\{name}"),
+        None if c.is_control() => format!("#\\x{:x}", u32::from(c)),
+        None => format!("#\\{c}"),
+    }
+}
+
+//- (test "char->integer" (char->integer #\A) 65)
+//- (test "char->integer" (char->integer #\newline) 10)
+//- (test "char->integer" (char->integer #\x3bb) 955)
+pub fn fn_char_to_integer(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Char(c)] => Ok(Node::Number(i64::from(u32::from(*c)))),
+        _ => invalid_arguments!("char->integer", arguments, ["[Char(c)]"]),
+    }
+}
+
+//- (test "integer->char" (integer->char 97) #\a)
+//- (test "integer->char" (integer->char 32) #\space)
+pub fn fn_integer_to_char(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Number(n)] => u32::try_from(*n)
+            .ok()
+            .and_then(char::from_u32)
+            .map(Node::Char)
+            .ok_or_else(|| format!("Invalid code point: {n}")),
+        _ => invalid_arguments!("integer->char", arguments, ["[Number(code-point)]"]),
+    }
+}
+
+// Text is classified by all of its characters, so predicates also work on
+// the multi-character graphemes returned by string->list
+fn classify(name: &str, arguments: &[Node], predicate: fn(char) -> bool) -> Result<Node, String> {
+    match arguments {
+        [Node::Char(c)] => Ok(Node::Bool(predicate(*c))),
+        [Node::Text(text)] => Ok(Node::Bool(!text.is_empty() && text.chars().all(predicate))),
+        _ => invalid_arguments!(name, arguments, ["[Char(c)]", "[Text(text)]"]),
+    }
+}
+
+//- (test "alphabetic?" (alphabetic? #\a) true)
+//- (test "alphabetic?" (alphabetic? #\é) true)
+//- (test "alphabetic?" (alphabetic? #\1) false)
+//- (test "alphabetic?" (alphabetic? "abc") true)
+pub fn fn_is_alphabetic(arguments: &[Node]) -> Result<Node, String> {
+    classify("alphabetic?", arguments, char::is_alphabetic)
+}
+
+//- (test "numeric?" (numeric? #\7) true)
+//- (test "numeric?" (numeric? #\x) false)
+//- (test "numeric?" (numeric? "") false)
+pub fn fn_is_numeric(arguments: &[Node]) -> Result<Node, String> {
+    classify("numeric?", arguments, char::is_numeric)
+}
+
+//- (test "whitespace?" (whitespace? #\space) true)
+//- (test "whitespace?" (whitespace? #\tab) true)
+//- (test "whitespace?" (whitespace? #\_) false)
+pub fn fn_is_whitespace(arguments: &[Node]) -> Result<Node, String> {
+    classify("whitespace?", arguments, char::is_whitespace)
+}
+
+//- (test "uppercase?" (uppercase? #\Q) true)
+//- (test "uppercase?" (uppercase? #\q) false)
+//- (test "uppercase?" (uppercase? #\Ä) true)
+pub fn fn_is_uppercase(arguments: &[Node]) -> Result<Node, String> {
+    classify("uppercase?", arguments, char::is_uppercase)
+}
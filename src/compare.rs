@@ -4,6 +4,11 @@ use crate::node::Node;
 //- (test "=" (= 1 2) false)
 //- (test "=" (= "foo" "bar") false)
 //- (test "=" (= 1 1) true)
+//- (test "=" (= #\a #\a) true)
+//- (test "=" (= (car (string->list "a")) "a") true)
+//- (test "=" (= "ab" #\a) false)
+//- (test "=" (= (lazy-range) (quote (0 1 2))) false)
+//- (test "=" (= (time 2025 1 1 12 0 0) (time 2025 1 1 7 0 0 -5)) true)
 pub fn fn_eq(arguments: &[Node]) -> Result<Node, String> {
     match arguments {
         [Node::Number(a), Node::Number(b)] => Ok(Node::Bool(a == b)),
@@ -13,7 +18,22 @@ pub fn fn_eq(arguments: &[Node]) -> Result<Node, String> {
             Ok(Node::Bool(a == b))
         }
         [Node::Float(a), Node::Float(b)] => Ok(Node::Bool(a == b)),
-        [Node::Time(t1, z1), Node::Time(t2, z2)] => Ok(Node::Bool(t1 == t2 && z1 == z2)),
+        // The same instant is equal whatever offset it is shown in
+        [Node::Time(t1, _), Node::Time(t2, _)] => Ok(Node::Bool(t1 == t2)),
+        [Node::Duration(a), Node::Duration(b)] => Ok(Node::Bool(a == b)),
+        [Node::Bytes(a), Node::Bytes(b)] => Ok(Node::Bool(a == b)),
+        [Node::Char(a), Node::Char(b)] => Ok(Node::Bool(a == b)),
+        [Node::Char(c), Node::Text(text)] | [Node::Text(text), Node::Char(c)] => {
+            Ok(Node::Bool(text.chars().eq(std::iter::once(*c))))
+        }
+        // One value past the list is enough to tell, even for an endless sequence
+        [Node::Lazy(seq), Node::List(list)] | [Node::List(list), Node::Lazy(seq)] => {
+            let values = seq
+                .iter()
+                .take(list.len() + 1)
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(Node::Bool(values == *list))
+        }
         _ => invalid_arguments!(
             "=",
             arguments,
@@ -23,7 +43,12 @@ pub fn fn_eq(arguments: &[Node]) -> Result<Node, String> {
                 "[List(a), List(b)]",
                 "[Bool(a), Bool(b)]",
                 "[Symbol(a), Symbol(b)]",
-                "[Time(t1, z1), Time(t2, z2)]"
+                "[Time(t1, z1), Time(t2, z2)]",
+                "[Duration(a), Duration(b)]",
+                "[Bytes(a), Bytes(b)]",
+                "[Char(a), Char(b)]",
+                "[Char(c), Text(text)]",
+                "[Lazy(seq), List(list)]"
             ]
         ),
     }
@@ -32,10 +57,25 @@ pub fn fn_eq(arguments: &[Node]) -> Result<Node, String> {
 //- (test "<" (< 1 2) true)
 //- (test "<" (< 2 1) false)
 //- (test "<" (< 1 1) false)
+//- (test "<" (< (minutes 59) (hours 1)) true)
+//- (test "<" (< #\a #\b) true)
+//- (test "<" (< (time 2025 1 1) (now)) true)
 pub fn fn_less_than(arguments: &[Node]) -> Result<Node, String> {
     match arguments {
-        [Node::Number(a), Node::Number(b)] => Ok(Node::Bool(a < b)),
-        _ => invalid_arguments!("<", arguments, ["[Number(a), Number(b)]"]),
+        [Node::Number(a), Node::Number(b)]
+        | [Node::Duration(a), Node::Duration(b)]
+        | [Node::Time(a, _), Node::Time(b, _)] => Ok(Node::Bool(a < b)),
+        [Node::Char(a), Node::Char(b)] => Ok(Node::Bool(a < b)),
+        _ => invalid_arguments!(
+            "<",
+            arguments,
+            [
+                "[Number(a), Number(b)]",
+                "[Duration(a), Duration(b)]",
+                "[Time(a, offset), Time(b, offset)]",
+                "[Char(a), Char(b)]"
+            ]
+        ),
     }
 }
 
@@ -44,8 +84,20 @@ pub fn fn_less_than(arguments: &[Node]) -> Result<Node, String> {
 //- (test ">" (> 1 1) false)
 pub fn fn_greater_than(arguments: &[Node]) -> Result<Node, String> {
     match arguments {
-        [Node::Number(a), Node::Number(b)] => Ok(Node::Bool(a > b)),
-        _ => invalid_arguments!(">", arguments, ["[Number(a), Number(b)]"]),
+        [Node::Number(a), Node::Number(b)]
+        | [Node::Duration(a), Node::Duration(b)]
+        | [Node::Time(a, _), Node::Time(b, _)] => Ok(Node::Bool(a > b)),
+        [Node::Char(a), Node::Char(b)] => Ok(Node::Bool(a > b)),
+        _ => invalid_arguments!(
+            ">",
+            arguments,
+            [
+                "[Number(a), Number(b)]",
+                "[Duration(a), Duration(b)]",
+                "[Time(a, offset), Time(b, offset)]",
+                "[Char(a), Char(b)]"
+            ]
+        ),
     }
 }
 
@@ -54,18 +106,43 @@ pub fn fn_greater_than(arguments: &[Node]) -> Result<Node, String> {
 //- (test "<=" (<= 1 1) true)
 pub fn fn_less_than_or_equal(arguments: &[Node]) -> Result<Node, String> {
     match arguments {
-        [Node::Number(a), Node::Number(b)] => Ok(Node::Bool(a <= b)),
-        _ => invalid_arguments!("<=", arguments, ["[Number(a), Number(b)]"]),
+        [Node::Number(a), Node::Number(b)]
+        | [Node::Duration(a), Node::Duration(b)]
+        | [Node::Time(a, _), Node::Time(b, _)] => Ok(Node::Bool(a <= b)),
+        [Node::Char(a), Node::Char(b)] => Ok(Node::Bool(a <= b)),
+        _ => invalid_arguments!(
+            "<=",
+            arguments,
+            [
+                "[Number(a), Number(b)]",
+                "[Duration(a), Duration(b)]",
+                "[Time(a, offset), Time(b, offset)]",
+                "[Char(a), Char(b)]"
+            ]
+        ),
     }
 }
 
 //- (test ">=" (>= 1 2) false)
 //- (test ">=" (>= 2 1) true)
 //- (test ">=" (>= 1 1) true)
+//- (test ">=" (>= (time 2025 1 1 12 0 0) (time 2025 1 1 7 0 0 -5)) true)
 pub fn fn_greater_than_or_equal(arguments: &[Node]) -> Result<Node, String> {
     match arguments {
-        [Node::Number(a), Node::Number(b)] => Ok(Node::Bool(a >= b)),
-        _ => invalid_arguments!(">=", arguments, ["[Number(a), Number(b)]"]),
+        [Node::Number(a), Node::Number(b)]
+        | [Node::Duration(a), Node::Duration(b)]
+        | [Node::Time(a, _), Node::Time(b, _)] => Ok(Node::Bool(a >= b)),
+        [Node::Char(a), Node::Char(b)] => Ok(Node::Bool(a >= b)),
+        _ => invalid_arguments!(
+            ">=",
+            arguments,
+            [
+                "[Number(a), Number(b)]",
+                "[Duration(a), Duration(b)]",
+                "[Time(a, offset), Time(b, offset)]",
+                "[Char(a), Char(b)]"
+            ]
+        ),
     }
 }
 
@@ -97,3 +174,54 @@ pub fn fn_or(arguments: &[Node]) -> Result<Node, String> {
         _ => invalid_arguments!("or", arguments, ["[Bool(a), Bool(b)]"]),
     }
 }
+
+fn type_rank(node: &Node) -> u8 {
+    match node {
+        Node::Bool(_) => 0,
+        Node::Number(_) | Node::Float(_) => 1,
+        Node::Text(_) => 2,
+        Node::Symbol(_) => 3,
+        Node::Time(_, _) => 4,
+        Node::List(_) => 5,
+        Node::Regex(_) => 6,
+        Node::Function(_) => 7,
+        Node::Lazy(_) => 8,
+        Node::File(_) => 9,
+        Node::Bytes(_) => 10,
+        Node::Rng(_) => 11,
+        Node::Duration(_) => 12,
+        Node::Job(_) => 13,
+        Node::Char(_) => 14,
+    }
+}
+
+// Total ordering used for sorting; values of unrelated types are ordered by type
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn compare(a: &Node, b: &Node) -> std::cmp::Ordering {
+    match (a, b) {
+        (Node::Bool(a), Node::Bool(b)) => a.cmp(b),
+        (Node::Number(a), Node::Number(b)) => a.cmp(b),
+        (Node::Float(a), Node::Float(b)) => a.total_cmp(b),
+        (Node::Number(a), Node::Float(b)) => (*a as f64).total_cmp(b),
+        (Node::Float(a), Node::Number(b)) => a.total_cmp(&(*b as f64)),
+        (Node::Text(a), Node::Text(b))
+        | (Node::Symbol(a), Node::Symbol(b))
+        | (Node::Regex(a), Node::Regex(b)) => a.cmp(b),
+        (Node::Time(a, _), Node::Time(b, _)) => a.cmp(b),
+        (Node::Duration(a), Node::Duration(b)) => a.cmp(b),
+        (Node::Job(a), Node::Job(b)) => a.cmp(b),
+        (Node::Bytes(a), Node::Bytes(b)) => a.cmp(b),
+        (Node::Char(a), Node::Char(b)) => a.cmp(b),
+        (Node::List(a), Node::List(b)) => {
+            for (x, y) in a.iter().zip(b) {
+                let ordering = compare(x, y);
+                if ordering != std::cmp::Ordering::Equal {
+                    return ordering;
+                }
+            }
+            a.len().cmp(&b.len())
+        }
+        _ => type_rank(a).cmp(&type_rank(b)),
+    }
+}
@@ -1,5 +1,6 @@
 use crate::invalid_arguments;
 use crate::node::Node;
+use unicode_segmentation::UnicodeSegmentation;
 
 //- (test "number->string" (number->string 1) "1")
 //- (test "number->string" (number->string 10000) "10000")
@@ -24,13 +25,21 @@ pub fn fn_string_to_number(arguments: &[Node]) -> Result<Node, String> {
     }
 }
 
-//- (test "string->list" (string->list "foo") (quote ("f" "o" "o")))
+// Graphemes made of several code points have no single character, so they
+// stay text; see string::grapheme_node
+//- (test "string->list" (string->list "foo") (quote (#\f #\o #\o)))
 //- (test "string->list" (string->list "") (quote ()))
-//- (test "string->list" (string->list "foo bar") (quote ("f" "o" "o" " " "b" "a" "r")))
+//- (test "string->list" (string->list "foo bar") (quote (#\f #\o #\o #\space #\b #\a #\r)))
+//- (test "string->list" (string->list "a👍🏽") (quote (#\a "👍🏽")))
+//- (test "string->list" (map (lambda (g) (type? g)) (string->list (nfc "é"))) (quote ("char")))
+//- (test "string->list" (map (lambda (g) (type? g)) (string->list (nfd "é"))) (quote ("text")))
 pub fn fn_string_to_list(arguments: &[Node]) -> Result<Node, String> {
     match arguments {
         [Node::Text(s)] => {
-            let list: Vec<Node> = s.chars().map(|c| Node::Text(c.to_string())).collect();
+            let list: Vec<Node> = s
+                .graphemes(true)
+                .map(crate::string::grapheme_node)
+                .collect();
             return Ok(Node::List(list));
         }
         _ => invalid_arguments!("string->list", arguments, ["[Text(s)]"]),
@@ -40,6 +49,8 @@ pub fn fn_string_to_list(arguments: &[Node]) -> Result<Node, String> {
 //- (test "list->string" (list->string (quote ("f" "o" "o"))) "foo")
 //- (test "list->string" (list->string (quote ())) "")
 //- (test "list->string" (list->string (quote ("f" "o" "o" " " "b" "a" "r"))) "foo bar")
+//- (test "list->string" (list->string (list #\h #\i #\newline)) "hi\n")
+//- (test "list->string" (list->string (string->list "añ👍🏽")) "añ👍🏽")
 pub fn fn_list_to_string(arguments: &[Node]) -> Result<Node, String> {
     match arguments {
         [Node::List(l)] => {
@@ -107,6 +118,16 @@ pub fn fn_time_to_string(arguments: &[Node]) -> Result<Node, String> {
     }
 }
 
+//- (test "duration->string" (duration->string (seconds 12000)) "3h 20m")
+//- (test "duration->string" (duration->string (duration "-1d 5s")) "-1d 5s")
+//- (test "duration->string" (duration->string (seconds 0)) "0s")
+pub fn fn_duration_to_string(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Duration(_)] => Ok(Node::Text(arguments[0].to_string())),
+        _ => invalid_arguments!("duration->string", arguments, ["[Duration(seconds)]"]),
+    }
+}
+
 pub fn fn_time_to_number(arguments: &[Node]) -> Result<Node, String> {
     match arguments {
         [Node::Time(seconds, _)] => {
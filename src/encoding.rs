@@ -0,0 +1,241 @@
+use crate::invalid_arguments;
+use crate::node::Node;
+use crate::random;
+use md5::Md5;
+use rand::Rng;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn hex_decode(text: &str) -> Result<Vec<u8>, String> {
+    if !text.len().is_multiple_of(2) {
+        return Err(format!("Hex string has odd length: {}", text.len()));
+    }
+    text.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            // from_str_radix alone would also accept a sign such as "+f"
+            Some(pair)
+                .filter(|pair| pair.iter().all(u8::is_ascii_hexdigit))
+                .and_then(|pair| std::str::from_utf8(pair).ok())
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| format!("Invalid hex digits: {}", String::from_utf8_lossy(pair)))
+        })
+        .collect()
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let indices = [
+            b[0] >> 2,
+            ((b[0] & 0x03) << 4) | (b[1] >> 4),
+            ((b[1] & 0x0f) << 2) | (b[2] >> 6),
+            b[2] & 0x3f,
+        ];
+        for (i, index) in indices.iter().enumerate() {
+            if i <= chunk.len() {
+                encoded.push(char::from(BASE64_ALPHABET[usize::from(*index)]));
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+// Padding is optional, but when present it must complete the last group of
+// four characters
+fn base64_decode(text: &str) -> Result<Vec<u8>, String> {
+    let text = text
+        .bytes()
+        .filter(|c| !c.is_ascii_whitespace())
+        .collect::<Vec<_>>();
+    let length = text.iter().rposition(|c| *c != b'=').map_or(0, |i| i + 1);
+    let padding = text.len() - length;
+    if padding > 2 || (padding > 0 && !text.len().is_multiple_of(4)) || length % 4 == 1 {
+        return Err(format!(
+            "Invalid base64 length: {} characters with {padding} padding",
+            text.len()
+        ));
+    }
+    let mut decoded = Vec::with_capacity(length * 3 / 4);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for &c in &text[..length] {
+        let value = BASE64_ALPHABET
+            .iter()
+            .position(|a| *a == c)
+            .ok_or_else(|| format!("Invalid base64 character: {}", char::from(c)))?;
+        buffer = (buffer << 6) | u32::try_from(value).map_err(|_| "Invalid base64")?;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push(u8::try_from((buffer >> bits) & 0xff).map_err(|_| "Invalid base64")?);
+        }
+    }
+    Ok(decoded)
+}
+
+fn url_encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for b in text.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b'~') {
+            encoded.push(char::from(b));
+        } else {
+            encoded.push_str(&format!("%{b:02X}"));
+        }
+    }
+    encoded
+}
+
+fn url_decode(text: &str) -> Result<String, String> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let digits = bytes
+                    .get(i + 1..i + 3)
+                    .ok_or_else(|| format!("Truncated percent escape at position {i}"))?;
+                decoded.extend(hex_decode(&String::from_utf8_lossy(digits))?);
+                i += 3;
+            }
+            b'+' => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            b => {
+                decoded.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| format!("URL-decoded text is not valid UTF-8: {text}"))
+}
+
+fn input_bytes<'a>(name: &str, arguments: &'a [Node]) -> Result<&'a [u8], String> {
+    match arguments {
+        [Node::Bytes(bytes)] => Ok(bytes),
+        [Node::Text(text)] => Ok(text.as_bytes()),
+        _ => invalid_arguments!(name, arguments, ["[Bytes(bytes)]", "[Text(text)]"]),
+    }
+}
+
+//- (test "hex-encode" (hex-encode (list->bytes (quote (0 171 255)))) "00abff")
+//- (test "hex-encode" (hex-encode (list->bytes (quote ()))) "")
+//- (test "hex-encode" (hex-encode "hi") "6869")
+pub fn fn_hex_encode(arguments: &[Node]) -> Result<Node, String> {
+    Ok(Node::Text(hex_encode(input_bytes(
+        "hex-encode",
+        arguments,
+    )?)))
+}
+
+//- (test "hex-decode" (bytes->list (hex-decode "00abFF")) (quote (0 171 255)))
+pub fn fn_hex_decode(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(text)] => hex_decode(text).map(Node::Bytes),
+        _ => invalid_arguments!("hex-decode", arguments, ["[Text(hex)]"]),
+    }
+}
+
+//- (test "base64-encode" (base64-encode (string->bytes "foobar")) "Zm9vYmFy")
+//- (test "base64-encode" (base64-encode (string->bytes "fooba")) "Zm9vYmE=")
+//- (test "base64-encode" (base64-encode (string->bytes "foob")) "Zm9vYg==")
+//- (test "base64-encode" (base64-encode "hello") "aGVsbG8=")
+pub fn fn_base64_encode(arguments: &[Node]) -> Result<Node, String> {
+    Ok(Node::Text(base64_encode(input_bytes(
+        "base64-encode",
+        arguments,
+    )?)))
+}
+
+//- (test "base64-decode" (base64-decode "Zm9vYmFy") (string->bytes "foobar"))
+//- (test "base64-decode" (base64-decode "Zm9vYg==") (string->bytes "foob"))
+//- (test "base64-decode" (base64-decode "") (string->bytes ""))
+//- (test "base64-decode" (base64-decode "Zm9vYg") (string->bytes "foob"))
+//- (test "base64-decode" (base64-decode "Zm9v\nYmFy") (string->bytes "foobar"))
+pub fn fn_base64_decode(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(text)] => base64_decode(text).map(Node::Bytes),
+        _ => invalid_arguments!("base64-decode", arguments, ["[Text(base64)]"]),
+    }
+}
+
+//- (test "url-encode" (url-encode "a b&c=d/é") "a%20b%26c%3Dd%2F%C3%A9")
+//- (test "url-encode" (url-encode "safe-_.~") "safe-_.~")
+pub fn fn_url_encode(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(text)] => Ok(Node::Text(url_encode(text))),
+        _ => invalid_arguments!("url-encode", arguments, ["[Text(text)]"]),
+    }
+}
+
+//- (test "url-decode" (url-decode "a%20b%26c%3Dd%2F%C3%A9") "a b&c=d/é")
+//- (test "url-decode" (url-decode "a+b") "a b")
+pub fn fn_url_decode(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(text)] => url_decode(text).map(Node::Text),
+        _ => invalid_arguments!("url-decode", arguments, ["[Text(text)]"]),
+    }
+}
+
+//- (test "sha256" (sha256 "abc") "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+//- (test "sha256" (sha256 (string->bytes "")) "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+pub fn fn_sha256(arguments: &[Node]) -> Result<Node, String> {
+    let bytes = input_bytes("sha256", arguments)?;
+    Ok(Node::Text(hex_encode(&Sha256::digest(bytes))))
+}
+
+//- (test "sha1" (sha1 "abc") "a9993e364706816aba3e25717850c26c9cd0d89d")
+pub fn fn_sha1(arguments: &[Node]) -> Result<Node, String> {
+    let bytes = input_bytes("sha1", arguments)?;
+    Ok(Node::Text(hex_encode(&Sha1::digest(bytes))))
+}
+
+//- (test "md5" (md5 "abc") "900150983cd24fb0d6963f7d28e17f72")
+pub fn fn_md5(arguments: &[Node]) -> Result<Node, String> {
+    let bytes = input_bytes("md5", arguments)?;
+    Ok(Node::Text(hex_encode(&Md5::digest(bytes))))
+}
+
+//- (test "crc32" (crc32 "123456789") 3421780262)
+//- (test "crc32" (crc32 (string->bytes "")) 0)
+pub fn fn_crc32(arguments: &[Node]) -> Result<Node, String> {
+    let bytes = input_bytes("crc32", arguments)?;
+    Ok(Node::Number(i64::from(crc32fast::hash(bytes))))
+}
+
+//- (test "uuid-v4" (length (uuid-v4)) 36)
+//- (test "uuid-v4" (substring (uuid-v4) 14 15) "4")
+//- (test "uuid-v4" (= (uuid-v4 (make-rng 3)) (uuid-v4 (make-rng 3))) true)
+pub fn fn_uuid_v4(arguments: &[Node]) -> Result<Node, String> {
+    let mut bytes: [u8; 16] = random::with_rng(arguments, |rng, arguments| match arguments {
+        [] => Ok(rng.random()),
+        _ => invalid_arguments!("uuid-v4", arguments, ["[]", "[Rng(rng)]"]),
+    })?;
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = hex_encode(&bytes);
+    Ok(Node::Text(format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )))
+}
@@ -58,6 +58,12 @@ impl Environment {
         }
     }
 
+    /// Give back the parent of a scope made with `from_parent`.
+    #[must_use]
+    pub fn into_parent(self) -> Option<Self> {
+        self.parent.map(|parent| *parent)
+    }
+
     pub fn insert(&mut self, name: &str, value: Node) {
         self.variables.insert(name.to_string(), value);
     }
@@ -1,6 +1,14 @@
 use crate::environment::Environment;
+use crate::file;
 use crate::invalid_arguments;
+use crate::lazy::Lazy;
+use crate::list;
 use crate::node::Node;
+use crate::scheduler;
+use crate::sequence;
+use crate::template;
+use crate::time;
+use std::rc::Rc;
 
 pub fn eval(node: &Node, env: &mut Environment) -> Result<Node, String> {
     match node {
@@ -10,10 +18,17 @@ pub fn eval(node: &Node, env: &mut Environment) -> Result<Node, String> {
         Node::Number(_)
         | Node::Text(_)
         | Node::Bool(_)
+        | Node::Char(_)
         | Node::Float(_)
         | Node::Function(_)
         | Node::Regex(_)
-        | Node::Time(_, _) => Ok(node.clone()),
+        | Node::Lazy(_)
+        | Node::File(_)
+        | Node::Rng(_)
+        | Node::Job(_)
+        | Node::Bytes(_)
+        | Node::Time(_, _)
+        | Node::Duration(_) => Ok(node.clone()),
         Node::List(nodes) => eval_list(nodes, env),
     }
 }
@@ -129,11 +144,18 @@ fn eval_get_type(rest: &[Node], env: &mut Environment) -> Result<Node, String> {
             Node::Text(_) => "text",
             Node::Float(_) => "float",
             Node::Bool(_) => "bool",
+            Node::Char(_) => "char",
             Node::Function(_) => "function",
             Node::Regex(_) => "regex",
             Node::Time(_, _) => "time",
+            Node::Duration(_) => "duration",
             Node::Symbol(_) => "symbol",
             Node::List(_) => "list",
+            Node::Lazy(_) => "lazy",
+            Node::File(_) => "file",
+            Node::Rng(_) => "rng",
+            Node::Job(_) => "job",
+            Node::Bytes(_) => "bytes",
         };
 
         Ok(Node::Text(type_name.to_string()))
@@ -181,6 +203,11 @@ fn eval_map(rest: &[Node], env: &mut Environment) -> Result<Node, String> {
             }
             Ok(Node::List(mapped))
         }
+        [function, Node::Lazy(seq)] => Ok(Node::Lazy(Rc::new(Lazy::Map {
+            function: function.clone(),
+            source: seq.clone(),
+            env: env.clone(),
+        }))),
         [function, Node::List(list), Node::List(args)] => {
             let mut mapped = Vec::new();
             for item in list {
@@ -195,6 +222,7 @@ fn eval_map(rest: &[Node], env: &mut Environment) -> Result<Node, String> {
             rest,
             [
                 "[Any(function), List(list)]",
+                "[Any(function), Lazy(seq)]",
                 "[Any(function), List(list), List(args)]"
             ]
         ),
@@ -219,7 +247,16 @@ fn eval_filter(rest: &[Node], env: &mut Environment) -> Result<Node, String> {
             }
             Ok(Node::List(filtered))
         }
-        _ => invalid_arguments!("filter", rest, ["[Any(function), List(list)]"]),
+        [function, Node::Lazy(seq)] => Ok(Node::Lazy(Rc::new(Lazy::Filter {
+            function: function.clone(),
+            source: seq.clone(),
+            env: env.clone(),
+        }))),
+        _ => invalid_arguments!(
+            "filter",
+            rest,
+            ["[Any(function), List(list)]", "[Any(function), Lazy(seq)]"]
+        ),
     }
 }
 
@@ -239,10 +276,20 @@ fn eval_fold(rest: &[Node], env: &mut Environment) -> Result<Node, String> {
             }
             Ok(result)
         }
+        [function, initial_value, Node::Lazy(seq)] => {
+            let mut result = initial_value.clone();
+            for item in seq.iter() {
+                result = apply(function, &[result, item?], env)?;
+            }
+            Ok(result)
+        }
         _ => invalid_arguments!(
             "fold",
             rest,
-            ["[Any(function), Any(initial_value), List(list)]"]
+            [
+                "[Any(function), Any(initial_value), List(list)]",
+                "[Any(function), Any(initial_value), Lazy(seq)]"
+            ]
         ),
     }
 }
@@ -406,6 +453,22 @@ fn eval_list(nodes: &[Node], env: &mut Environment) -> Result<Node, String> {
                 "let" => eval_let(rest, env)?,
                 "let-restricted" => eval_let_restricted(rest, env)?,
                 "time-ms" => eval_time_ms(rest, env)?,
+                "sort" => list::eval_sort(rest, env)?,
+                "sort-by" => list::eval_sort_by(rest, env)?,
+                "min-by" => list::eval_min_by(rest, env)?,
+                "max-by" => list::eval_max_by(rest, env)?,
+                "take-while" => sequence::eval_take_while(rest, env)?,
+                "drop-while" => sequence::eval_drop_while(rest, env)?,
+                "partition" => sequence::eval_partition(rest, env)?,
+                "group-by" => sequence::eval_group_by(rest, env)?,
+                "iterate" => sequence::eval_iterate(rest, env)?,
+                "with-open-file" => file::eval_with_open_file(rest, env)?,
+                "for-each-line" => file::eval_for_each_line(rest, env)?,
+                "bench" => time::eval_bench(rest, env)?,
+                "every" => scheduler::eval_every(rest, env)?,
+                "at" => scheduler::eval_at(rest, env)?,
+                "schedule" => scheduler::eval_schedule(rest, env)?,
+                "render-template" => template::eval_render_template(rest, env)?,
                 _ => {
                     let function = env
                         .lookup(first)
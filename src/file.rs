@@ -0,0 +1,300 @@
+use crate::environment::Environment;
+use crate::eval::{apply, eval};
+use crate::invalid_arguments;
+use crate::node::Node;
+use std::cell::RefCell;
+use std::io::{BufRead, Read, Write};
+use std::rc::Rc;
+
+enum Stream {
+    Reader(std::io::BufReader<std::fs::File>),
+    Writer(std::io::BufWriter<std::fs::File>),
+    Closed,
+}
+
+pub struct FileHandle {
+    path: String,
+    mode: String,
+    stream: Stream,
+}
+
+impl std::fmt::Debug for FileHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = if matches!(self.stream, Stream::Closed) {
+            "closed"
+        } else {
+            "open"
+        };
+        write!(f, "FileHandle({:?}, {}, {state})", self.path, self.mode)
+    }
+}
+
+impl PartialEq for FileHandle {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl FileHandle {
+    fn open(path: &str, mode: &str) -> Result<Self, String> {
+        let mut options = std::fs::OpenOptions::new();
+        match mode {
+            "read" | "r" => options.read(true),
+            "write" | "w" => options.write(true).create(true).truncate(true),
+            "append" | "a" => options.append(true).create(true),
+            _ => {
+                return Err(format!(
+                    "Invalid file mode: {mode} (expected read, write or append)"
+                ));
+            }
+        };
+        let file = options
+            .open(path)
+            .map_err(|e| format!("Failed to open file: {path}: {e}"))?;
+        let stream = if matches!(mode, "read" | "r") {
+            Stream::Reader(std::io::BufReader::new(file))
+        } else {
+            Stream::Writer(std::io::BufWriter::new(file))
+        };
+
+        Ok(Self {
+            path: path.to_string(),
+            mode: mode.to_string(),
+            stream,
+        })
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn reader(&mut self) -> Result<&mut std::io::BufReader<std::fs::File>, String> {
+        match &mut self.stream {
+            Stream::Reader(reader) => Ok(reader),
+            Stream::Writer(_) => Err(format!("File is not open for reading: {}", self.path)),
+            Stream::Closed => Err(format!("File is closed: {}", self.path)),
+        }
+    }
+
+    fn writer(&mut self) -> Result<&mut std::io::BufWriter<std::fs::File>, String> {
+        match &mut self.stream {
+            Stream::Writer(writer) => Ok(writer),
+            Stream::Reader(_) => Err(format!("File is not open for writing: {}", self.path)),
+            Stream::Closed => Err(format!("File is closed: {}", self.path)),
+        }
+    }
+
+    pub fn read_line(&mut self) -> Result<Option<String>, String> {
+        let path = self.path.clone();
+        let mut line = String::new();
+        let read = self
+            .reader()?
+            .read_line(&mut line)
+            .map_err(|e| format!("Failed to read line from {path}: {e}"))?;
+        if read == 0 {
+            return Ok(None);
+        }
+        if line.ends_with('\n') {
+            line.pop();
+            if line.ends_with('\r') {
+                line.pop();
+            }
+        }
+        Ok(Some(line))
+    }
+
+    fn read_char(&mut self) -> Result<Option<char>, String> {
+        let path = self.path.clone();
+        let reader = self.reader()?;
+        let mut buffer = [0u8; 4];
+        if reader
+            .read(&mut buffer[..1])
+            .map_err(|e| format!("Failed to read from {path}: {e}"))?
+            == 0
+        {
+            return Ok(None);
+        }
+        let width = match buffer[0] {
+            0x00..=0x7f => 1,
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            _ => 4,
+        };
+        reader
+            .read_exact(&mut buffer[1..width])
+            .map_err(|e| format!("Failed to read from {path}: {e}"))?;
+        std::str::from_utf8(&buffer[..width])
+            .map(|s| s.chars().next())
+            .map_err(|_| format!("Invalid UTF-8 in {path}"))
+    }
+
+    pub fn write(&mut self, text: &str) -> Result<(), String> {
+        let path = self.path.clone();
+        self.writer()?
+            .write_all(text.as_bytes())
+            .map_err(|e| format!("Failed to write to {path}: {e}"))
+    }
+
+    fn flush(&mut self) -> Result<(), String> {
+        let path = self.path.clone();
+        self.writer()?
+            .flush()
+            .map_err(|e| format!("Failed to flush {path}: {e}"))
+    }
+
+    fn is_eof(&mut self) -> Result<bool, String> {
+        let path = self.path.clone();
+        Ok(self
+            .reader()?
+            .fill_buf()
+            .map_err(|e| format!("Failed to read from {path}: {e}"))?
+            .is_empty())
+    }
+
+    fn close(&mut self) -> Result<(), String> {
+        match std::mem::replace(&mut self.stream, Stream::Closed) {
+            Stream::Writer(mut writer) => writer
+                .flush()
+                .map_err(|e| format!("Failed to flush {}: {e}", self.path)),
+            Stream::Reader(_) | Stream::Closed => Ok(()),
+        }
+    }
+}
+
+fn open(name: &str, arguments: &[Node]) -> Result<Rc<RefCell<FileHandle>>, String> {
+    let handle = match arguments {
+        [Node::Text(path)] => FileHandle::open(path, "read")?,
+        [Node::Text(path), Node::Text(mode)] => FileHandle::open(path, mode)?,
+        _ => {
+            return invalid_arguments!(
+                name,
+                arguments,
+                ["[Text(path)]", "[Text(path), Text(mode)]"]
+            );
+        }
+    };
+    Ok(Rc::new(RefCell::new(handle)))
+}
+
+//- (test "open" (type? (open "Cargo.toml" "read")) "file")
+pub fn fn_open(arguments: &[Node]) -> Result<Node, String> {
+    Ok(Node::File(open("open", arguments)?))
+}
+
+//- (test "read-chars" (read-chars 3 (open "Cargo.toml")) "[pa")
+//- (test "read-chars" (let ((f (temp-file))) (begin (write-file f "héllo") (let ((result (read-chars 2 (open f)))) (begin (delete-file f) result)))) "hé")
+//- (test "read-chars" (let ((f (temp-file))) (let ((result (read-chars 2 (open f)))) (begin (delete-file f) result))) ())
+pub fn fn_read_chars(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Number(count), Node::File(handle)] => {
+            let count = usize::try_from(*count).map_err(|_| format!("Invalid count: {count}"))?;
+            let mut handle = handle.borrow_mut();
+            let mut text = String::new();
+            for _ in 0..count {
+                match handle.read_char()? {
+                    Some(c) => text.push(c),
+                    None => break,
+                }
+            }
+            if text.is_empty() && count > 0 {
+                return Ok(Node::List(vec![]));
+            }
+            Ok(Node::Text(text))
+        }
+        _ => invalid_arguments!("read-chars", arguments, ["[Number(count), File(handle)]"]),
+    }
+}
+
+//- (test "flush" (let ((f (temp-file))) (let ((h (open f "write"))) (begin (write h "foo") (flush h) (let ((result (read-file f))) (begin (delete-file f) result))))) "foo")
+pub fn fn_flush(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::File(handle)] => {
+            handle.borrow_mut().flush()?;
+            Ok(Node::Bool(true))
+        }
+        _ => invalid_arguments!("flush", arguments, ["[File(handle)]"]),
+    }
+}
+
+//- (test "close" (let ((f (temp-file))) (let ((h (open f "write"))) (begin (write-line h "foo") (close h) (let ((result (read-file f))) (begin (delete-file f) result))))) "foo\n")
+pub fn fn_close(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::File(handle)] => {
+            handle.borrow_mut().close()?;
+            Ok(Node::Bool(true))
+        }
+        _ => invalid_arguments!("close", arguments, ["[File(handle)]"]),
+    }
+}
+
+//- (test "eof?" (let ((f (temp-file))) (let ((result (eof? (open f)))) (begin (delete-file f) result))) true)
+//- (test "eof?" (eof? (open "Cargo.toml")) false)
+pub fn fn_is_eof(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::File(handle)] => Ok(Node::Bool(handle.borrow_mut().is_eof()?)),
+        _ => invalid_arguments!("eof?", arguments, ["[File(handle)]"]),
+    }
+}
+
+//- (test "with-open-file" (with-open-file (f "Cargo.toml") (read-line f)) "[package]")
+//- (test "with-open-file" (let ((path (temp-file))) (begin (with-open-file (f path "append") (write f "a")) (with-open-file (f path "append") (write f "b")) (let ((result (read-file path))) (begin (delete-file path) result)))) "ab")
+pub fn eval_with_open_file(rest: &[Node], env: &mut Environment) -> Result<Node, String> {
+    let [Node::List(spec), body] = rest else {
+        return invalid_arguments!(
+            "with-open-file",
+            rest,
+            ["[(Symbol(name) Text(path) Text(mode)?), Any(body)]"]
+        );
+    };
+    let Some((Node::Symbol(name), open_arguments)) = spec.split_first() else {
+        return Err(format!("Invalid binding in with-open-file: {spec:?}"));
+    };
+
+    let open_arguments = open_arguments
+        .iter()
+        .map(|n| eval(n, env))
+        .collect::<Result<Vec<_>, _>>()?;
+    let handle = open("with-open-file", &open_arguments)?;
+
+    let mut new_env = Environment::from_parent(env.clone());
+    new_env.insert(name, Node::File(handle.clone()));
+    let result = eval(body, &mut new_env);
+    let closed = handle.borrow_mut().close();
+
+    let result = result?;
+    closed?;
+    Ok(result)
+}
+
+//- (test "for-each-line" (let ((f (temp-file))) (begin (write-file f "a\nb\n") (for-each-line (lambda (line) (append-file (concat f ".out") (upper line))) f) (let ((result (read-file (concat f ".out")))) (begin (delete-file f) (delete-file (concat f ".out")) result)))) "AB")
+//- (test "for-each-line" (for-each-line (lambda (line) line) (open "Cargo.toml")) true)
+pub fn eval_for_each_line(rest: &[Node], env: &mut Environment) -> Result<Node, String> {
+    let rest = rest
+        .iter()
+        .map(|n| eval(n, env))
+        .collect::<Result<Vec<_>, _>>()?;
+    let handle = match &rest[..] {
+        [_, Node::Text(path)] => Rc::new(RefCell::new(FileHandle::open(path, "read")?)),
+        [_, Node::File(handle)] => handle.clone(),
+        _ => {
+            return invalid_arguments!(
+                "for-each-line",
+                rest,
+                [
+                    "[Any(function), Text(path)]",
+                    "[Any(function), File(handle)]"
+                ]
+            );
+        }
+    };
+
+    loop {
+        let line = handle.borrow_mut().read_line()?;
+        let Some(line) = line else {
+            break;
+        };
+        apply(&rest[0], &[Node::Text(line)], env)?;
+    }
+
+    Ok(Node::Bool(true))
+}
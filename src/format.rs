@@ -0,0 +1,327 @@
+use crate::invalid_arguments;
+use crate::list::record_get;
+use crate::node::Node;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+// Widths and precisions beyond this are mistakes rather than layouts
+const MAX_WIDTH: usize = 100_000;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+/// A parsed `{argument:spec}` placeholder, following Rust's format syntax:
+/// `[[fill]align][+][#][0][width][.precision][type]`.
+#[derive(Default)]
+struct Spec {
+    fill: Option<char>,
+    align: Option<Align>,
+    plus: bool,
+    alternate: bool,
+    zero: bool,
+    width: usize,
+    precision: Option<usize>,
+    kind: String,
+}
+
+enum Argument {
+    Next,
+    Index(usize),
+    Name(String),
+}
+
+fn parse_spec(spec: &str) -> Result<Spec, String> {
+    let invalid = || format!("Invalid format spec: {{:{spec}}}");
+    let mut result = Spec::default();
+    let chars = spec.chars().collect::<Vec<_>>();
+    let to_align = |c: char| match c {
+        '<' => Some(Align::Left),
+        '>' => Some(Align::Right),
+        '^' => Some(Align::Center),
+        _ => None,
+    };
+    let mut i = 0;
+    if let Some(align) = chars.get(1).copied().and_then(to_align) {
+        result.fill = Some(chars[0]);
+        result.align = Some(align);
+        i = 2;
+    } else if let Some(align) = chars.first().copied().and_then(to_align) {
+        result.align = Some(align);
+        i = 1;
+    }
+    if chars.get(i) == Some(&'+') {
+        result.plus = true;
+        i += 1;
+    }
+    if chars.get(i) == Some(&'#') {
+        result.alternate = true;
+        i += 1;
+    }
+    if chars.get(i) == Some(&'0') {
+        result.zero = true;
+        i += 1;
+    }
+    let digits = |i: &mut usize| {
+        let start = *i;
+        while chars.get(*i).is_some_and(char::is_ascii_digit) {
+            *i += 1;
+        }
+        chars[start..*i].iter().collect::<String>()
+    };
+    let width = digits(&mut i);
+    if !width.is_empty() {
+        result.width = width.parse().map_err(|_| invalid())?;
+    }
+    if chars.get(i) == Some(&'.') {
+        i += 1;
+        result.precision = Some(digits(&mut i).parse().map_err(|_| invalid())?);
+    }
+    if result.width > MAX_WIDTH || result.precision.is_some_and(|p| p > MAX_WIDTH) {
+        return Err(format!(
+            "Format width or precision larger than {MAX_WIDTH}: {{:{spec}}}"
+        ));
+    }
+    result.kind = chars[i..].iter().collect();
+    if !matches!(result.kind.as_str(), "" | "?" | "x" | "X" | "o" | "b" | "e") {
+        return Err(invalid());
+    }
+    Ok(result)
+}
+
+/// The form of a value that reads back as the same value, e.g. quoted text.
+fn readable(node: &Node) -> String {
+    match node {
+        Node::Text(text) => format!("{text:?}"),
+        Node::Char(c) => crate::character::to_name(*c),
+        Node::List(items) => format!(
+            "({})",
+            items.iter().map(readable).collect::<Vec<_>>().join(" ")
+        ),
+        Node::Float(f) if f.fract() == 0.0 && f.is_finite() => format!("{f:.1}"),
+        _ => node.to_string(),
+    }
+}
+
+fn exponent(f: f64, precision: Option<usize>) -> String {
+    match precision {
+        Some(precision) => format!("{f:.precision$e}"),
+        None => format!("{f:e}"),
+    }
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn render(node: &Node, spec: &Spec) -> Result<String, String> {
+    let mismatch = || format!("Cannot format {node} with {{:{}}}", spec.kind);
+    let mut text = match (spec.kind.as_str(), node) {
+        ("?", _) => readable(node),
+        ("x", Node::Number(n)) => format!("{n:x}"),
+        ("X", Node::Number(n)) => format!("{n:X}"),
+        ("o", Node::Number(n)) => format!("{n:o}"),
+        ("b", Node::Number(n)) => format!("{n:b}"),
+        ("e", Node::Number(n)) => exponent(*n as f64, spec.precision),
+        ("e", Node::Float(f)) => exponent(*f, spec.precision),
+        ("x" | "X" | "o" | "b" | "e", _) => return Err(mismatch()),
+        (_, Node::Float(f)) => match spec.precision {
+            Some(precision) => format!("{f:.precision$}"),
+            None => f.to_string(),
+        },
+        (_, Node::Number(n)) => match spec.precision {
+            Some(precision) => format!("{:.precision$}", *n as f64),
+            None => n.to_string(),
+        },
+        (_, Node::Text(text)) => match spec.precision {
+            Some(precision) => text.graphemes(true).take(precision).collect(),
+            None => text.clone(),
+        },
+        _ => node.to_string(),
+    };
+
+    let numeric = matches!(node, Node::Number(_) | Node::Float(_));
+    if spec.alternate {
+        let prefix = match spec.kind.as_str() {
+            "x" | "X" => "0x",
+            "o" => "0o",
+            "b" => "0b",
+            _ => "",
+        };
+        text = match text.strip_prefix('-') {
+            Some(digits) => format!("-{prefix}{digits}"),
+            None => format!("{prefix}{text}"),
+        };
+    }
+    if spec.plus && numeric && !text.starts_with('-') {
+        text.insert(0, '+');
+    }
+    if spec.zero && numeric && spec.align.is_none() {
+        // Zeros go between the sign or radix prefix and the digits
+        let mut prefix = usize::from(text.starts_with(['+', '-']));
+        if spec.alternate && matches!(spec.kind.as_str(), "x" | "X" | "o" | "b") {
+            prefix += 2;
+        }
+        let missing = spec.width.saturating_sub(text.width());
+        text.insert_str(prefix, &"0".repeat(missing));
+        return Ok(text);
+    }
+    let default_align = if numeric { Align::Right } else { Align::Left };
+    Ok(pad(
+        &text,
+        spec.width,
+        spec.fill.unwrap_or(' '),
+        spec.align.unwrap_or(default_align),
+    ))
+}
+
+/// Pad `text` with `fill` to `width` terminal columns.
+fn pad(text: &str, width: usize, fill: char, align: Align) -> String {
+    let missing = width.saturating_sub(text.width());
+    let (left, right) = match align {
+        Align::Left => (0, missing),
+        Align::Right => (missing, 0),
+        Align::Center => (missing / 2, missing - missing / 2),
+    };
+    let fill = fill.to_string();
+    format!("{}{text}{}", fill.repeat(left), fill.repeat(right))
+}
+
+fn format_text(template: &str, arguments: &[Node]) -> Result<String, String> {
+    let named = match arguments.last() {
+        Some(Node::List(record)) => Some(record),
+        _ => None,
+    };
+    let mut output = String::new();
+    let mut next = 0;
+    let mut chars = template.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        match c {
+            '{' if chars.peek().map(|(_, c)| *c) == Some('{') => {
+                chars.next();
+                output.push('{');
+            }
+            '}' if chars.peek().map(|(_, c)| *c) == Some('}') => {
+                chars.next();
+                output.push('}');
+            }
+            '{' => {
+                let end = template[start..]
+                    .find('}')
+                    .map(|offset| start + offset)
+                    .ok_or_else(|| format!("Unclosed placeholder in format string: {template}"))?;
+                let placeholder = &template[start + 1..end];
+                while chars.peek().is_some_and(|(index, _)| *index <= end) {
+                    chars.next();
+                }
+                let (argument, spec) = placeholder.split_once(':').unwrap_or((placeholder, ""));
+                let argument = if argument.is_empty() {
+                    Argument::Next
+                } else if let Ok(index) = argument.parse::<usize>() {
+                    Argument::Index(index)
+                } else {
+                    Argument::Name(argument.to_string())
+                };
+                let value = match argument {
+                    Argument::Next => {
+                        next += 1;
+                        arguments.get(next - 1)
+                    }
+                    Argument::Index(index) => arguments.get(index),
+                    Argument::Name(ref name) => {
+                        named.and_then(|record| record_get(record, &Node::Symbol(name.clone())))
+                    }
+                }
+                .ok_or_else(|| format!("Missing argument for placeholder {{{placeholder}}}"))?;
+                output.push_str(&render(value, &parse_spec(spec)?)?);
+            }
+            '}' => return Err(format!("Unmatched '}}' in format string: {template}")),
+            _ => output.push(c),
+        }
+    }
+    Ok(output)
+}
+
+// Placeholders are {} for the next argument, {2} for a position or {name}
+// for a field of a trailing record, with an optional spec after a colon
+//- (test "sprintf" (sprintf "{} + {} = {}" 1 2 3) "1 + 2 = 3")
+//- (test "sprintf" (sprintf "[{:>6}|{:<6}|{:^6}]" "ab" "cd" "ef") "[    ab|cd    |  ef  ]")
+//- (test "sprintf" (sprintf "{:.2}" 3.14159) "3.14")
+//- (test "sprintf" (sprintf "{:.2}" 5) "5.00")
+//- (test "sprintf" (sprintf "{:08x}" 255) "000000ff")
+//- (test "sprintf" (sprintf "{:#06x}" 255) "0x00ff")
+//- (test "sprintf" (sprintf "{:+05}" 42) "+0042")
+//- (test "sprintf" (sprintf "{:05}" -42) "-0042")
+//- (test "sprintf" (sprintf "{:*^7}" "mid") "**mid**")
+//- (test "sprintf" (sprintf "{:?}" (list 1 1.0 (list 2))) "(1 1.0 (2))")
+//- (test "sprintf" (string-length (sprintf "{:?}" "ab")) 4)
+//- (test "sprintf" (sprintf "{:?}" (list #\a #\space)) "(#\a #\space)")
+//- (test "sprintf" (sprintf "{1} {0} {1}" "a" "b") "b a b")
+//- (test "sprintf" (sprintf "{name} is {age:>3}" (quote ((name "Ann") (age 7)))) "Ann is   7")
+//- (test "sprintf" (sprintf "{{literal}} {}" 1) "{literal} 1")
+//- (test "sprintf" (sprintf "{:b}" 5) "101")
+//- (test "sprintf" (sprintf "{:.2e}" 5) "5.00e0")
+//- (test "sprintf" (sprintf "{:.1e}" 1234.5) "1.2e3")
+//- (test "sprintf" (sprintf "{:>4}" "日本") "日本")
+pub fn fn_sprintf(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(template), rest @ ..] => format_text(template, rest).map(Node::Text),
+        _ => invalid_arguments!("sprintf", arguments, ["[Text(template), Any(argument)...]"]),
+    }
+}
+
+//- (test "printf" (printf "{}" "") true)
+pub fn fn_printf(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(template), rest @ ..] => {
+            print!("{}", format_text(template, rest)?);
+            Ok(Node::Bool(true))
+        }
+        _ => invalid_arguments!("printf", arguments, ["[Text(template), Any(argument)...]"]),
+    }
+}
+
+fn pad_function(name: &str, arguments: &[Node], align: Align) -> Result<Node, String> {
+    let (width, fill, text) = match arguments {
+        [Node::Number(width), Node::Text(text)] => (width, " ", text),
+        [Node::Number(width), Node::Text(fill), Node::Text(text)] => (width, fill.as_str(), text),
+        _ => {
+            return invalid_arguments!(
+                name,
+                arguments,
+                [
+                    "[Number(width), Text(text)]",
+                    "[Number(width), Text(fill), Text(text)]"
+                ]
+            );
+        }
+    };
+    let width = usize::try_from(*width)
+        .ok()
+        .filter(|width| *width <= MAX_WIDTH)
+        .ok_or_else(|| format!("Invalid width: {width} (expected 0-{MAX_WIDTH})"))?;
+    let mut fill_chars = fill.chars();
+    let (Some(fill), None) = (fill_chars.next(), fill_chars.next()) else {
+        return Err(format!("Fill must be a single character: '{fill}'"));
+    };
+    Ok(Node::Text(pad(text, width, fill, align)))
+}
+
+//- (test "pad-left" (pad-left 5 "ab") "   ab")
+//- (test "pad-left" (pad-left 5 "0" "42") "00042")
+//- (test "pad-left" (pad-left 1 "abc") "abc")
+pub fn fn_pad_left(arguments: &[Node]) -> Result<Node, String> {
+    pad_function("pad-left", arguments, Align::Right)
+}
+
+//- (test "pad-right" (pad-right 5 "ab") "ab   ")
+//- (test "pad-right" (pad-right 4 "." "é") "é...")
+pub fn fn_pad_right(arguments: &[Node]) -> Result<Node, String> {
+    pad_function("pad-right", arguments, Align::Left)
+}
+
+//- (test "center" (center 6 "ab") "  ab  ")
+//- (test "center" (center 5 "-" "ab") "-ab--")
+pub fn fn_center(arguments: &[Node]) -> Result<Node, String> {
+    pad_function("center", arguments, Align::Center)
+}
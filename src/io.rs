@@ -1,6 +1,12 @@
 use crate::invalid_arguments;
+use crate::list::make_record;
 use crate::node::Node;
+use rand::Rng;
+use std::io::Write;
+use std::os::unix::fs::PermissionsExt;
 
+//- (test "format" (format "a" 1 "b") "a1b")
+//- (test "format" (format "a {" 1) "a {1")
 pub fn fn_format(arguments: &[Node]) -> Result<Node, String> {
     Ok(Node::Text(
         arguments
@@ -10,7 +16,16 @@ pub fn fn_format(arguments: &[Node]) -> Result<Node, String> {
     ))
 }
 
+// Arguments are turned into text before the handle is borrowed, since
+// displaying a handle borrows it too
+//- (test "write" (let ((f (temp-file))) (let ((h (open f "write"))) (begin (write h h) (flush h) (let ((result (starts-with? "file(" (read-file f)))) (begin (delete-file f) result))))) true)
 pub fn fn_write(arguments: &[Node]) -> Result<Node, String> {
+    if let [Node::File(handle), rest @ ..] = arguments {
+        let text = rest.iter().map(ToString::to_string).collect::<String>();
+        handle.borrow_mut().write(&text)?;
+        return Ok(Node::Bool(true));
+    }
+
     for arg in arguments {
         print!("{arg}");
     }
@@ -19,6 +34,13 @@ pub fn fn_write(arguments: &[Node]) -> Result<Node, String> {
 }
 
 pub fn fn_write_line(arguments: &[Node]) -> Result<Node, String> {
+    if let [Node::File(handle), rest @ ..] = arguments {
+        let mut text = rest.iter().map(ToString::to_string).collect::<String>();
+        text.push('\n');
+        handle.borrow_mut().write(&text)?;
+        return Ok(Node::Bool(true));
+    }
+
     for arg in arguments {
         print!("{arg}");
     }
@@ -31,7 +53,7 @@ pub fn fn_write_file(arguments: &[Node]) -> Result<Node, String> {
     match arguments {
         [Node::Text(filename), Node::Text(contents)] => {
             std::fs::write(filename, contents)
-                .map_err(|_| format!("Failed to write file: {filename}"))?;
+                .map_err(|e| format!("Failed to write file: {filename}: {e}"))?;
             Ok(Node::Bool(true))
         }
         _ => invalid_arguments!(
@@ -42,7 +64,16 @@ pub fn fn_write_file(arguments: &[Node]) -> Result<Node, String> {
     }
 }
 
-pub fn fn_read_line(_: &[Node]) -> Result<Node, String> {
+//- (test "read-line" (let ((f (open "Cargo.toml"))) (begin (read-line f) (read-line f))) `name = "lich"`)
+//- (test "read-line" (let ((f (temp-file))) (let ((result (read-line (open f)))) (begin (delete-file f) result))) ())
+pub fn fn_read_line(arguments: &[Node]) -> Result<Node, String> {
+    if let [Node::File(handle)] = arguments {
+        return Ok(handle
+            .borrow_mut()
+            .read_line()?
+            .map_or_else(|| Node::List(vec![]), Node::Text));
+    }
+
     let mut input = String::new();
     std::io::stdin()
         .read_line(&mut input)
@@ -54,7 +85,7 @@ pub fn fn_read_file(arguments: &[Node]) -> Result<Node, String> {
     match arguments {
         [Node::Text(filename)] => {
             let input_string = std::fs::read_to_string(filename)
-                .map_err(|_| format!("Failed to read file: {filename}"))?;
+                .map_err(|e| format!("Failed to read file: {filename}: {e}"))?;
             Ok(Node::Text(input_string))
         }
         _ => invalid_arguments!("read-file", arguments, ["[Text(filename)]"]),
@@ -65,7 +96,7 @@ pub fn fn_ls(arguments: &[Node]) -> Result<Node, String> {
     match arguments {
         [Node::Text(path)] => {
             let entries = std::fs::read_dir(path)
-                .map_err(|_| format!("Failed to read directory: {path}"))?
+                .map_err(|e| format!("Failed to read directory: {path}: {e}"))?
                 .filter_map(std::result::Result::ok)
                 .map(|entry| Node::Text(entry.file_name().to_string_lossy().to_string()))
                 .collect::<Vec<Node>>();
@@ -86,3 +117,297 @@ pub fn fn_is_directory(arguments: &[Node]) -> Result<Node, String> {
         _ => invalid_arguments!("is-directory", arguments, ["[Text(path)]"]),
     }
 }
+
+//- (test "append-file" (let ((f (temp-file))) (begin (write-file f "foo") (append-file f "bar") (let ((result (read-file f))) (begin (delete-file f) result)))) "foobar")
+pub fn fn_append_file(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(filename), Node::Text(contents)] => {
+            std::fs::OpenOptions::new()
+                .append(true)
+                .create(true)
+                .open(filename)
+                .and_then(|mut file| file.write_all(contents.as_bytes()))
+                .map_err(|e| format!("Failed to append to file: {filename}: {e}"))?;
+            Ok(Node::Bool(true))
+        }
+        _ => invalid_arguments!(
+            "append-file",
+            arguments,
+            ["[Text(filename), Text(contents)]"]
+        ),
+    }
+}
+
+//- (test "file-exists?" (file-exists? "Cargo.toml") true)
+//- (test "file-exists?" (file-exists? "does/not/exist") false)
+pub fn fn_file_exists(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(path)] => Ok(Node::Bool(std::path::Path::new(path).exists())),
+        _ => invalid_arguments!("file-exists?", arguments, ["[Text(path)]"]),
+    }
+}
+
+//- (test "file-size" (let ((f (temp-file))) (begin (write-file f "foo") (let ((result (file-size f))) (begin (delete-file f) result)))) 3)
+pub fn fn_file_size(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(path)] => {
+            let metadata = std::fs::metadata(path)
+                .map_err(|e| format!("Failed to read metadata: {path}: {e}"))?;
+            i64::try_from(metadata.len())
+                .map(Node::Number)
+                .map_err(|_| format!("File size too large: {path}"))
+        }
+        _ => invalid_arguments!("file-size", arguments, ["[Text(path)]"]),
+    }
+}
+
+fn modified_time(path: &str, metadata: &std::fs::Metadata) -> Result<Node, String> {
+    let modified = metadata
+        .modified()
+        .map_err(|e| format!("Failed to read modification time: {path}: {e}"))?;
+    let seconds = match modified.duration_since(std::time::UNIX_EPOCH) {
+        Ok(duration) => i64::try_from(duration.as_secs()),
+        Err(e) => i64::try_from(e.duration().as_secs()).map(|s| -s),
+    }
+    .map_err(|_| format!("Modification time out of range: {path}"))?;
+    Ok(Node::Time(seconds, 0))
+}
+
+//- (test "file-mtime" (type? (file-mtime "Cargo.toml")) "time")
+pub fn fn_file_mtime(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(path)] => {
+            let metadata = std::fs::metadata(path)
+                .map_err(|e| format!("Failed to read metadata: {path}: {e}"))?;
+            modified_time(path, &metadata)
+        }
+        _ => invalid_arguments!("file-mtime", arguments, ["[Text(path)]"]),
+    }
+}
+
+//- (test "copy-file" (let ((f (temp-file)) (g (temp-file))) (begin (write-file f "foo") (copy-file f g) (let ((result (read-file g))) (begin (delete-file f) (delete-file g) result)))) "foo")
+pub fn fn_copy_file(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(from), Node::Text(to)] => {
+            std::fs::copy(from, to)
+                .map_err(|e| format!("Failed to copy file: {from} to {to}: {e}"))?;
+            Ok(Node::Bool(true))
+        }
+        _ => invalid_arguments!("copy-file", arguments, ["[Text(from), Text(to)]"]),
+    }
+}
+
+//- (test "move-file" (let ((f (temp-file)) (g (temp-file))) (begin (move-file f g) (let ((result (file-exists? f))) (begin (delete-file g) result)))) false)
+pub fn fn_move_file(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(from), Node::Text(to)] => {
+            std::fs::rename(from, to)
+                .map_err(|e| format!("Failed to move file: {from} to {to}: {e}"))?;
+            Ok(Node::Bool(true))
+        }
+        _ => invalid_arguments!("move-file", arguments, ["[Text(from), Text(to)]"]),
+    }
+}
+
+//- (test "delete-file" (let ((f (temp-file))) (begin (delete-file f) (file-exists? f))) false)
+pub fn fn_delete_file(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(path)] => {
+            std::fs::remove_file(path)
+                .map_err(|e| format!("Failed to delete file: {path}: {e}"))?;
+            Ok(Node::Bool(true))
+        }
+        _ => invalid_arguments!("delete-file", arguments, ["[Text(path)]"]),
+    }
+}
+
+//- (test "mkdir" (let ((d (temp-dir))) (begin (mkdir (concat d "/a/b")) (let ((result (directory? (concat d "/a/b")))) (begin (rmdir d true) result)))) true)
+pub fn fn_mkdir(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(path)] => {
+            std::fs::create_dir_all(path)
+                .map_err(|e| format!("Failed to create directory: {path}: {e}"))?;
+            Ok(Node::Bool(true))
+        }
+        _ => invalid_arguments!("mkdir", arguments, ["[Text(path)]"]),
+    }
+}
+
+//- (test "rmdir" (let ((d (temp-dir))) (begin (rmdir d) (file-exists? d))) false)
+//- (test "rmdir" (let ((d (temp-dir))) (begin (mkdir (concat d "/a")) (rmdir d true) (file-exists? d))) false)
+pub fn fn_rmdir(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(path)] => {
+            std::fs::remove_dir(path)
+                .map_err(|e| format!("Failed to remove directory: {path}: {e}"))?;
+            Ok(Node::Bool(true))
+        }
+        [Node::Text(path), Node::Bool(recursive)] => {
+            if *recursive {
+                std::fs::remove_dir_all(path)
+            } else {
+                std::fs::remove_dir(path)
+            }
+            .map_err(|e| format!("Failed to remove directory: {path}: {e}"))?;
+            Ok(Node::Bool(true))
+        }
+        _ => invalid_arguments!(
+            "rmdir",
+            arguments,
+            ["[Text(path)]", "[Text(path), Bool(recursive)]"]
+        ),
+    }
+}
+
+fn walk_dir(
+    path: &std::path::Path,
+    filter: Option<&regex::Regex>,
+    files: &mut Vec<String>,
+) -> Result<(), String> {
+    let entries = std::fs::read_dir(path)
+        .map_err(|e| format!("Failed to read directory: {}: {e}", path.display()))?;
+    for entry in entries {
+        let entry =
+            entry.map_err(|e| format!("Failed to read directory: {}: {e}", path.display()))?;
+        let file_type = entry
+            .file_type()
+            .map_err(|e| format!("Failed to read metadata: {}: {e}", entry.path().display()))?;
+        let entry_path = entry.path();
+        if file_type.is_dir() {
+            walk_dir(&entry_path, filter, files)?;
+        } else {
+            let entry_path = entry_path.to_string_lossy().to_string();
+            if filter.is_none_or(|re| re.is_match(&entry_path)) {
+                files.push(entry_path);
+            }
+        }
+    }
+    Ok(())
+}
+
+//- (test "walk-dir" (let ((d (temp-dir))) (begin (mkdir (concat d "/a")) (write-file (concat d "/a/x.rs") "") (write-file (concat d "/y.txt") "") (let ((result (length (walk-dir d)))) (begin (rmdir d true) result)))) 2)
+//- (test "walk-dir" (let ((d (temp-dir))) (begin (write-file (concat d "/x.rs") "") (write-file (concat d "/y.txt") "") (let ((result (= (walk-dir d (regex "rs$")) (list (concat d "/x.rs"))))) (begin (rmdir d true) result)))) true)
+pub fn fn_walk_dir(arguments: &[Node]) -> Result<Node, String> {
+    let (path, filter) = match arguments {
+        [Node::Text(path)] => (path, None),
+        [Node::Text(path), Node::Regex(r)] => (
+            path,
+            Some(regex::Regex::new(r).map_err(|e| format!("Invalid regex: {e}"))?),
+        ),
+        _ => {
+            return invalid_arguments!(
+                "walk-dir",
+                arguments,
+                ["[Text(path)]", "[Text(path), Regex(filter)]"]
+            );
+        }
+    };
+
+    let mut files = Vec::new();
+    walk_dir(std::path::Path::new(path), filter.as_ref(), &mut files)?;
+    files.sort();
+    Ok(Node::List(files.into_iter().map(Node::Text).collect()))
+}
+
+//- (test "glob" (glob "src/mai*.rs") (quote ("src/main.rs")))
+//- (test "glob" (glob "does/not/*.exist") (quote ()))
+pub fn fn_glob(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(pattern)] => {
+            let paths = glob::glob(pattern)
+                .map_err(|e| format!("Invalid glob pattern: {pattern}: {e}"))?
+                .map(|entry| {
+                    entry
+                        .map(|path| Node::Text(path.to_string_lossy().to_string()))
+                        .map_err(|e| format!("Failed to read path: {e}"))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(Node::List(paths))
+        }
+        _ => invalid_arguments!("glob", arguments, ["[Text(pattern)]"]),
+    }
+}
+
+fn temp_path() -> std::path::PathBuf {
+    let suffix: String = (0..12)
+        .map(|_| char::from(rand::rng().sample(rand::distr::Alphanumeric)))
+        .collect();
+    std::env::temp_dir().join(format!("lich-{suffix}"))
+}
+
+pub fn fn_temp_file(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [] => {
+            let path = temp_path();
+            std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+                .map_err(|e| format!("Failed to create temporary file: {}: {e}", path.display()))?;
+            Ok(Node::Text(path.to_string_lossy().to_string()))
+        }
+        _ => invalid_arguments!("temp-file", arguments, ["[]"]),
+    }
+}
+
+pub fn fn_temp_dir(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [] => {
+            let path = temp_path();
+            std::fs::create_dir(&path).map_err(|e| {
+                format!(
+                    "Failed to create temporary directory: {}: {e}",
+                    path.display()
+                )
+            })?;
+            Ok(Node::Text(path.to_string_lossy().to_string()))
+        }
+        _ => invalid_arguments!("temp-dir", arguments, ["[]"]),
+    }
+}
+
+fn permission_string(mode: u32) -> String {
+    let mut permissions = String::new();
+    for shift in [6, 3, 0] {
+        let bits = (mode >> shift) & 0o7;
+        permissions.push(if bits & 0o4 == 0 { '-' } else { 'r' });
+        permissions.push(if bits & 0o2 == 0 { '-' } else { 'w' });
+        permissions.push(if bits & 0o1 == 0 { '-' } else { 'x' });
+    }
+    permissions
+}
+
+//- (test "file-info" (get (quote type) (file-info "src")) "directory")
+//- (test "file-info" (get (quote permissions) (let ((f (temp-file))) (begin (run "chmod" (list "640" f)) (let ((result (file-info f))) (begin (delete-file f) result))))) "rw-r-----")
+pub fn fn_file_info(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(path)] => {
+            let metadata = std::fs::symlink_metadata(path)
+                .map_err(|e| format!("Failed to read metadata: {path}: {e}"))?;
+            let file_type = metadata.file_type();
+            let kind = if file_type.is_symlink() {
+                "symlink"
+            } else if file_type.is_dir() {
+                "directory"
+            } else if file_type.is_file() {
+                "file"
+            } else {
+                "other"
+            };
+            let mode = metadata.permissions().mode();
+            let size = i64::try_from(metadata.len())
+                .map_err(|_| format!("File size too large: {path}"))?;
+
+            Ok(make_record(vec![
+                ("path", Node::Text(path.clone())),
+                ("type", Node::Text(kind.to_string())),
+                ("size", Node::Number(size)),
+                ("permissions", Node::Text(permission_string(mode))),
+                ("mode", Node::Number(i64::from(mode & 0o7777))),
+                ("readonly", Node::Bool(metadata.permissions().readonly())),
+                ("modified", modified_time(path, &metadata)?),
+            ]))
+        }
+        _ => invalid_arguments!("file-info", arguments, ["[Text(path)]"]),
+    }
+}
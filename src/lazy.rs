@@ -0,0 +1,317 @@
+use crate::environment::Environment;
+use crate::eval::apply;
+use crate::invalid_arguments;
+use crate::node::Node;
+use std::io::BufRead;
+use std::rc::Rc;
+
+// A lazy sequence is a recipe for producing values rather than the values
+// themselves. Functions are applied in the environment captured when the
+// sequence was built, so a sequence can be realized anywhere, even while printing.
+//
+// Values are not cached: every consumer runs the recipe again from the start,
+// and printing runs it for the first few values. Functions with side effects,
+// such as writing output, run once per consumer; realize the sequence first to
+// run them only once.
+pub enum Lazy {
+    Range {
+        start: i64,
+        end: Option<i64>,
+        step: i64,
+    },
+    Iterate {
+        function: Node,
+        seed: Node,
+        env: Environment,
+    },
+    Lines(String),
+    Map {
+        function: Node,
+        source: Rc<Lazy>,
+        env: Environment,
+    },
+    Filter {
+        function: Node,
+        source: Rc<Lazy>,
+        env: Environment,
+    },
+    Take {
+        count: usize,
+        source: Rc<Lazy>,
+    },
+    Drop {
+        count: usize,
+        source: Rc<Lazy>,
+    },
+    TakeWhile {
+        function: Node,
+        source: Rc<Lazy>,
+        env: Environment,
+    },
+    DropWhile {
+        function: Node,
+        source: Rc<Lazy>,
+        env: Environment,
+    },
+}
+
+// How many values are shown when a sequence is printed
+const PREVIEW_LENGTH: usize = 10;
+
+impl std::fmt::Debug for Lazy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Range { start, end, step } => match end {
+                Some(end) => write!(f, "Lazy(range {start} {end} {step})"),
+                None => write!(f, "Lazy(range {start} .. {step})"),
+            },
+            Self::Iterate { function, seed, .. } => {
+                write!(f, "Lazy(iterate {function:?} {seed:?})")
+            }
+            Self::Lines(path) => write!(f, "Lazy(lines-of-file {path:?})"),
+            Self::Map {
+                function, source, ..
+            } => write!(f, "Lazy(map {function:?} {source:?})"),
+            Self::Filter {
+                function, source, ..
+            } => write!(f, "Lazy(filter {function:?} {source:?})"),
+            Self::Take { count, source } => write!(f, "Lazy(take {count} {source:?})"),
+            Self::Drop { count, source } => write!(f, "Lazy(drop {count} {source:?})"),
+            Self::TakeWhile {
+                function, source, ..
+            } => write!(f, "Lazy(take-while {function:?} {source:?})"),
+            Self::DropWhile {
+                function, source, ..
+            } => write!(f, "Lazy(drop-while {function:?} {source:?})"),
+        }
+    }
+}
+
+impl PartialEq for Lazy {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+fn test(name: &str, function: &Node, item: &Node, env: &mut Environment) -> Result<bool, String> {
+    match apply(function, std::slice::from_ref(item), env)? {
+        Node::Bool(b) => Ok(b),
+        other => Err(format!(
+            "Predicate for {name} must return a boolean: {other:?}"
+        )),
+    }
+}
+
+impl Lazy {
+    pub fn iter(&self) -> Box<dyn Iterator<Item = Result<Node, String>> + '_> {
+        match self {
+            Self::Range { start, end, step } => {
+                let (end, step) = (*end, *step);
+                let mut current = Some(*start);
+                Box::new(std::iter::from_fn(move || {
+                    let value = current?;
+                    if end
+                        .is_some_and(|end| (step > 0 && value >= end) || (step < 0 && value <= end))
+                    {
+                        return None;
+                    }
+                    current = value.checked_add(step);
+                    Some(Ok(Node::Number(value)))
+                }))
+            }
+            Self::Iterate {
+                function,
+                seed,
+                env,
+            } => {
+                let mut env = env.clone();
+                let mut previous: Option<Node> = None;
+                let mut done = false;
+                Box::new(std::iter::from_fn(move || {
+                    if done {
+                        return None;
+                    }
+                    let next = match previous.take() {
+                        None => Ok(seed.clone()),
+                        Some(previous) => apply(function, &[previous], &mut env),
+                    };
+                    match next {
+                        Ok(value) => {
+                            previous = Some(value.clone());
+                            Some(Ok(value))
+                        }
+                        Err(e) => {
+                            done = true;
+                            Some(Err(e))
+                        }
+                    }
+                }))
+            }
+            Self::Lines(path) => match std::fs::File::open(path) {
+                Ok(file) => Box::new(std::io::BufReader::new(file).lines().map(move |line| {
+                    line.map(Node::Text)
+                        .map_err(|e| format!("Failed to read line from {path}: {e}"))
+                })),
+                Err(e) => Box::new(std::iter::once(Err(format!(
+                    "Failed to open file: {path}: {e}"
+                )))),
+            },
+            Self::Map {
+                function,
+                source,
+                env,
+            } => {
+                let mut env = env.clone();
+                Box::new(
+                    source
+                        .iter()
+                        .map(move |item| apply(function, &[item?], &mut env)),
+                )
+            }
+            Self::Filter {
+                function,
+                source,
+                env,
+            } => {
+                let mut env = env.clone();
+                Box::new(source.iter().filter_map(move |item| {
+                    let item = match item {
+                        Ok(item) => item,
+                        Err(e) => return Some(Err(e)),
+                    };
+                    match test("filter", function, &item, &mut env) {
+                        Ok(true) => Some(Ok(item)),
+                        Ok(false) => None,
+                        Err(e) => Some(Err(e)),
+                    }
+                }))
+            }
+            Self::Take { count, source } => Box::new(source.iter().take(*count)),
+            Self::Drop { count, source } => Box::new(source.iter().skip(*count)),
+            Self::TakeWhile {
+                function,
+                source,
+                env,
+            } => {
+                let mut env = env.clone();
+                let mut done = false;
+                Box::new(source.iter().map_while(move |item| {
+                    if done {
+                        return None;
+                    }
+                    let result = item.and_then(|item| {
+                        Ok(test("take-while", function, &item, &mut env)?.then_some(item))
+                    });
+                    match result {
+                        Ok(Some(item)) => Some(Ok(item)),
+                        Ok(None) => None,
+                        Err(e) => {
+                            done = true;
+                            Some(Err(e))
+                        }
+                    }
+                }))
+            }
+            Self::DropWhile {
+                function,
+                source,
+                env,
+            } => {
+                let mut env = env.clone();
+                let mut dropping = true;
+                Box::new(source.iter().filter_map(move |item| {
+                    let item = match item {
+                        Ok(item) => item,
+                        Err(e) => return Some(Err(e)),
+                    };
+                    if dropping {
+                        match test("drop-while", function, &item, &mut env) {
+                            Ok(true) => return None,
+                            Ok(false) => dropping = false,
+                            Err(e) => return Some(Err(e)),
+                        }
+                    }
+                    Some(Ok(item))
+                }))
+            }
+        }
+    }
+
+    pub fn realize(&self) -> Result<Vec<Node>, String> {
+        self.iter().collect()
+    }
+
+    /// The first `limit` values, or an error if the sequence is longer.
+    pub fn realize_at_most(&self, limit: usize) -> Result<Vec<Node>, String> {
+        let values = self.iter().take(limit + 1).collect::<Result<Vec<_>, _>>()?;
+        if values.len() > limit {
+            return Err(format!("Sequence has more than {limit} values"));
+        }
+        Ok(values)
+    }
+
+    /// A printable form showing only the first few values, so that printing
+    /// an infinite sequence terminates.
+    pub fn preview(&self) -> String {
+        let mut values = Vec::new();
+        for value in self.iter().take(PREVIEW_LENGTH + 1) {
+            match value {
+                Ok(value) => values.push(value.to_string()),
+                Err(e) => return format!("lazy(error: {e})"),
+            }
+        }
+        if values.len() > PREVIEW_LENGTH {
+            values[PREVIEW_LENGTH] = "...".to_string();
+        }
+        format!("lazy({})", values.join(" "))
+    }
+}
+
+//- (test "lazy-range" (realize (take 3 (lazy-range))) (quote (0 1 2)))
+//- (test "lazy-range" (realize (lazy-range 2 5)) (quote (2 3 4)))
+//- (test "lazy-range" (realize (lazy-range 5 0 -2)) (quote (5 3 1)))
+//- (test "lazy-range" (length (lazy-range 0 100000000 1000)) 100000)
+//- (test "lazy-range" (format (lazy-range 2 5)) "lazy(2 3 4)")
+//- (test "lazy-range" (format (lazy-range)) "lazy(0 1 2 3 4 5 6 7 8 9 ...)")
+pub fn fn_lazy_range(arguments: &[Node]) -> Result<Node, String> {
+    let (start, end, step) = match arguments {
+        [] => (0, None, 1),
+        [Node::Number(start)] => (*start, None, 1),
+        [Node::Number(start), Node::Number(end)] => (*start, Some(*end), 1),
+        [Node::Number(start), Node::Number(end), Node::Number(step)] => (*start, Some(*end), *step),
+        _ => {
+            return invalid_arguments!(
+                "lazy-range",
+                arguments,
+                [
+                    "[]",
+                    "[Number(start)]",
+                    "[Number(start), Number(end)]",
+                    "[Number(start), Number(end), Number(step)]"
+                ]
+            );
+        }
+    };
+    if step == 0 {
+        return Err("Step cannot be zero".to_string());
+    }
+    Ok(Node::Lazy(Rc::new(Lazy::Range { start, end, step })))
+}
+
+pub fn fn_lines_of_file(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(filename)] => Ok(Node::Lazy(Rc::new(Lazy::Lines(filename.clone())))),
+        _ => invalid_arguments!("lines-of-file", arguments, ["[Text(filename)]"]),
+    }
+}
+
+//- (test "realize" (realize (map inc (lazy-range 0 3))) (quote (1 2 3)))
+//- (test "realize" (realize (filter even? (lazy-range 0 5))) (quote (0 2 4)))
+//- (test "realize" (realize (quote (1 2))) (quote (1 2)))
+pub fn fn_realize(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Lazy(seq)] => Ok(Node::List(seq.realize()?)),
+        [Node::List(list)] => Ok(Node::List(list.clone())),
+        _ => invalid_arguments!("realize", arguments, ["[Lazy(seq)]", "[List(list)]"]),
+    }
+}
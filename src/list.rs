@@ -1,4 +1,9 @@
+use crate::compare::compare;
+use crate::environment::Environment;
+use crate::eval::{apply, eval};
+use crate::invalid_arguments;
 use crate::node::Node;
+use unicode_segmentation::UnicodeSegmentation;
 
 //- (test "car" (car (quote (1 2 3))) 1)
 //- (test "car" (car (quote ())) ())
@@ -48,6 +53,8 @@ pub fn fn_cons(arguments: &[Node]) -> Result<Node, String> {
 //- (test "length" (length (quote (1 2 3))) 3)
 //- (test "length" (length (quote ())) 0)
 //- (test "length" (length (quote (1))) 1)
+//- (test "length" (length (string->bytes "héllo")) 6)
+//- (test "length" (length "héllo") 5)
 pub fn fn_length(arguments: &[Node]) -> Result<Node, String> {
     if arguments.len() == 1 {
         if let Node::List(list) = &arguments[0] {
@@ -58,8 +65,25 @@ pub fn fn_length(arguments: &[Node]) -> Result<Node, String> {
             return Err("Failed to convert length".to_string());
         }
 
+        if let Node::Lazy(seq) = &arguments[0] {
+            let mut n: i64 = 0;
+            for item in seq.iter() {
+                item?;
+                n += 1;
+            }
+            return Ok(Node::Number(n));
+        }
+
+        if let Node::Bytes(bytes) = &arguments[0] {
+            let n = i64::try_from(bytes.len());
+            if let Ok(n) = n {
+                return Ok(Node::Number(n));
+            }
+            return Err("Failed to convert length".to_string());
+        }
+
         if let Node::Text(text) = &arguments[0] {
-            let n = i64::try_from(text.len());
+            let n = i64::try_from(text.graphemes(true).count());
             if let Ok(n) = n {
                 return Ok(Node::Number(n));
             }
@@ -132,3 +156,228 @@ pub fn fn_nth(arguments: &[Node]) -> Result<Node, String> {
     }
     Err("Invalid arguments for nth".to_string())
 }
+
+fn merge_sort(
+    items: Vec<Node>,
+    less: &mut impl FnMut(&Node, &Node) -> Result<bool, String>,
+) -> Result<Vec<Node>, String> {
+    if items.len() <= 1 {
+        return Ok(items);
+    }
+
+    let mut left = items;
+    let right = left.split_off(left.len() / 2);
+    let left = merge_sort(left, less)?;
+    let right = merge_sort(right, less)?;
+
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let mut left = left.into_iter().peekable();
+    let mut right = right.into_iter().peekable();
+    while let (Some(l), Some(r)) = (left.peek(), right.peek()) {
+        if less(r, l)? {
+            merged.extend(right.next());
+        } else {
+            merged.extend(left.next());
+        }
+    }
+    merged.extend(left);
+    merged.extend(right);
+
+    Ok(merged)
+}
+
+//- (test "sort" (sort (quote (3 1 2))) (quote (1 2 3)))
+//- (test "sort" (sort (quote ("b" "c" "a"))) (quote ("a" "b" "c")))
+//- (test "sort" (sort (quote (2 1.5 3))) (quote (1.5 2 3)))
+//- (test "sort" (sort (quote ())) (quote ()))
+//- (test "sort" (sort (lambda (a b) (> a b)) (quote (1 3 2))) (quote (3 2 1)))
+//- (test "sort" (sort (lambda (a b) (< (car a) (car b))) (quote ((1 b) (0 c) (1 a)))) (quote ((0 c) (1 b) (1 a))))
+pub fn eval_sort(rest: &[Node], env: &mut Environment) -> Result<Node, String> {
+    let rest = rest
+        .iter()
+        .map(|n| eval(n, env))
+        .collect::<Result<Vec<_>, _>>()?;
+    match &rest[..] {
+        [Node::List(list)] => {
+            let mut sorted = list.clone();
+            sorted.sort_by(compare);
+            Ok(Node::List(sorted))
+        }
+        [function, Node::List(list)] => {
+            let sorted = merge_sort(list.clone(), &mut |a, b| match apply(
+                function,
+                &[a.clone(), b.clone()],
+                env,
+            )? {
+                Node::Bool(less) => Ok(less),
+                other => Err(format!("Comparator must return a boolean: {other:?}")),
+            })?;
+            Ok(Node::List(sorted))
+        }
+        _ => invalid_arguments!(
+            "sort",
+            rest,
+            ["[List(list)]", "[Any(comparator), List(list)]"]
+        ),
+    }
+}
+
+fn keyed(function: &Node, list: &[Node], env: &mut Environment) -> Result<Vec<Node>, String> {
+    list.iter()
+        .map(|item| apply(function, std::slice::from_ref(item), env))
+        .collect()
+}
+
+//- (test "sort-by" (sort-by length (quote ((1 2) (1) ()))) (quote (() (1) (1 2))))
+//- (test "sort-by" (sort-by abs (quote (-3 1 -2))) (quote (1 -2 -3)))
+//- (test "sort-by" (sort-by car (quote ((1 b) (0 c) (1 a)))) (quote ((0 c) (1 b) (1 a))))
+pub fn eval_sort_by(rest: &[Node], env: &mut Environment) -> Result<Node, String> {
+    let rest = rest
+        .iter()
+        .map(|n| eval(n, env))
+        .collect::<Result<Vec<_>, _>>()?;
+    match &rest[..] {
+        [function, Node::List(list)] => {
+            let keys = keyed(function, list, env)?;
+            let mut indices = (0..list.len()).collect::<Vec<_>>();
+            indices.sort_by(|a, b| compare(&keys[*a], &keys[*b]));
+            Ok(Node::List(
+                indices.into_iter().map(|i| list[i].clone()).collect(),
+            ))
+        }
+        _ => invalid_arguments!("sort-by", rest, ["[Any(function), List(list)]"]),
+    }
+}
+
+//- (test "reverse" (reverse (quote (1 2 3))) (quote (3 2 1)))
+//- (test "reverse" (reverse (quote ())) (quote ()))
+//- (test "reverse" (reverse (quote (1))) (quote (1)))
+pub fn fn_reverse(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::List(list)] => Ok(Node::List(list.iter().rev().cloned().collect())),
+        _ => invalid_arguments!("reverse", arguments, ["[List(list)]"]),
+    }
+}
+
+//- (test "binary-search" (binary-search 3 (quote (1 2 3 4))) 2)
+//- (test "binary-search" (binary-search "b" (quote ("a" "b" "c"))) 1)
+//- (test "binary-search" (binary-search 5 (quote (1 2 3 4))) ())
+//- (test "binary-search" (binary-search 0 (quote ())) ())
+pub fn fn_binary_search(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [item, Node::List(list)] => match list.binary_search_by(|probe| compare(probe, item)) {
+            Ok(index) => i64::try_from(index)
+                .map(Node::Number)
+                .map_err(|_| "Failed to convert index".to_string()),
+            Err(_) => Ok(Node::List(vec![])),
+        },
+        _ => invalid_arguments!("binary-search", arguments, ["[Any(item), List(sorted)]"]),
+    }
+}
+
+//- (test "unique" (unique (quote (1 2 1 3 2))) (quote (1 2 3)))
+//- (test "unique" (unique (quote ())) (quote ()))
+//- (test "unique" (unique (quote ("a" "a"))) (quote ("a")))
+pub fn fn_unique(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::List(list)] => {
+            let mut unique: Vec<Node> = Vec::new();
+            for item in list {
+                if !unique.contains(item) {
+                    unique.push(item.clone());
+                }
+            }
+            Ok(Node::List(unique))
+        }
+        _ => invalid_arguments!("unique", arguments, ["[List(list)]"]),
+    }
+}
+
+//- (test "dedupe" (dedupe (quote (1 1 2 2 1))) (quote (1 2 1)))
+//- (test "dedupe" (dedupe (quote ())) (quote ()))
+//- (test "dedupe" (dedupe (quote (1 2 3))) (quote (1 2 3)))
+pub fn fn_dedupe(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::List(list)] => {
+            let mut deduped = list.clone();
+            deduped.dedup();
+            Ok(Node::List(deduped))
+        }
+        _ => invalid_arguments!("dedupe", arguments, ["[List(list)]"]),
+    }
+}
+
+fn extreme_by(
+    name: &str,
+    rest: &[Node],
+    env: &mut Environment,
+    keep: std::cmp::Ordering,
+) -> Result<Node, String> {
+    let rest = rest
+        .iter()
+        .map(|n| eval(n, env))
+        .collect::<Result<Vec<_>, _>>()?;
+    match &rest[..] {
+        [function, Node::List(list)] => {
+            if list.is_empty() {
+                return Err(format!("Cannot compute {name} of an empty list"));
+            }
+            let keys = keyed(function, list, env)?;
+            let mut best = 0;
+            for i in 1..list.len() {
+                if compare(&keys[i], &keys[best]) == keep {
+                    best = i;
+                }
+            }
+            Ok(list[best].clone())
+        }
+        _ => invalid_arguments!(name, rest, ["[Any(function), List(list)]"]),
+    }
+}
+
+//- (test "min-by" (min-by abs (quote (-3 1 -2))) 1)
+//- (test "min-by" (min-by length (quote ((1 2) (3) (4)))) (quote (3)))
+pub fn eval_min_by(rest: &[Node], env: &mut Environment) -> Result<Node, String> {
+    extreme_by("min-by", rest, env, std::cmp::Ordering::Less)
+}
+
+//- (test "max-by" (max-by abs (quote (-3 1 -2))) -3)
+//- (test "max-by" (max-by length (quote ((1 2) (3 4) (5)))) (quote (1 2)))
+pub fn eval_max_by(rest: &[Node], env: &mut Environment) -> Result<Node, String> {
+    extreme_by("max-by", rest, env, std::cmp::Ordering::Greater)
+}
+
+pub fn make_record(fields: Vec<(&str, Node)>) -> Node {
+    Node::List(
+        fields
+            .into_iter()
+            .map(|(key, value)| Node::List(vec![Node::Symbol(key.to_string()), value]))
+            .collect(),
+    )
+}
+
+fn key_matches(key: &Node, candidate: &Node) -> bool {
+    match (key, candidate) {
+        (Node::Symbol(a) | Node::Text(a), Node::Symbol(b) | Node::Text(b)) => a == b,
+        _ => key == candidate,
+    }
+}
+
+pub fn record_get<'a>(record: &'a [Node], key: &Node) -> Option<&'a Node> {
+    record.iter().find_map(|field| match field {
+        Node::List(pair) if pair.len() == 2 && key_matches(key, &pair[0]) => Some(&pair[1]),
+        _ => None,
+    })
+}
+
+//- (test "get" (get (quote b) (quote ((a 1) (b 2)))) 2)
+//- (test "get" (get "a" (quote ((a 1) (b 2)))) 1)
+//- (test "get" (get (quote c) (quote ((a 1)))) ())
+pub fn fn_get(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [key, Node::List(record)] => Ok(record_get(record, key)
+            .cloned()
+            .unwrap_or_else(|| Node::List(vec![]))),
+        _ => invalid_arguments!("get", arguments, ["[Any(key), List(record)]"]),
+    }
+}
@@ -1,18 +1,29 @@
 pub mod arithmetic;
+pub mod bytes;
+pub mod character;
 pub mod compare;
 pub mod conversion;
+pub mod encoding;
 pub mod environment;
 pub mod error;
 pub mod eval;
+pub mod file;
+pub mod format;
 pub mod io;
+pub mod lazy;
 pub mod list;
+pub mod math;
 pub mod node;
 pub mod parse;
+pub mod path;
 pub mod random;
 pub mod regex;
+pub mod scheduler;
 pub mod sequence;
+pub mod stats;
 pub mod string;
 pub mod system;
+pub mod template;
 pub mod terminal;
 pub mod time;
 pub mod tree;
@@ -167,6 +178,8 @@ fn process_files(positional_args: &Vec<&String>, env: &mut Environment, verbose:
             }
         }
     }
+
+    scheduler::run_event_loop(env, |e| eprintln!("{RED}{e}{NORMAL}"));
 }
 
 fn create_environment(env: &mut Environment) {
@@ -189,6 +202,23 @@ fn create_environment(env: &mut Environment) {
     env.add_function("negate", arithmetic::fn_negate);
     env.add_function("sqrt", arithmetic::fn_sqrt);
 
+    // Bytes
+    env.add_function("read-bytes", bytes::fn_read_bytes);
+    env.add_function("write-bytes", bytes::fn_write_bytes);
+    env.add_function("bytes->list", bytes::fn_bytes_to_list);
+    env.add_function("list->bytes", bytes::fn_list_to_bytes);
+    env.add_function("bytes-slice", bytes::fn_bytes_slice);
+    env.add_function("bytes->string", bytes::fn_bytes_to_string);
+    env.add_function("string->bytes", bytes::fn_string_to_bytes);
+
+    // Characters
+    env.add_function("char->integer", character::fn_char_to_integer);
+    env.add_function("integer->char", character::fn_integer_to_char);
+    env.add_function("alphabetic?", character::fn_is_alphabetic);
+    env.add_function("numeric?", character::fn_is_numeric);
+    env.add_function("whitespace?", character::fn_is_whitespace);
+    env.add_function("uppercase?", character::fn_is_uppercase);
+
     // Comparison
     env.add_function("=", compare::fn_eq);
     env.add_function("<", compare::fn_less_than);
@@ -209,9 +239,30 @@ fn create_environment(env: &mut Environment) {
     env.add_function("string->boolean", conversion::fn_string_to_boolean);
     env.add_function("boolean->string", conversion::fn_boolean_to_string);
     env.add_function("time->string", conversion::fn_time_to_string);
+    env.add_function("duration->string", conversion::fn_duration_to_string);
     env.add_function("time->number", conversion::fn_time_to_number);
     env.add_function("number->float", conversion::fn_number_to_float);
 
+    // Encoding
+    env.add_function("hex-encode", encoding::fn_hex_encode);
+    env.add_function("hex-decode", encoding::fn_hex_decode);
+    env.add_function("base64-encode", encoding::fn_base64_encode);
+    env.add_function("base64-decode", encoding::fn_base64_decode);
+    env.add_function("url-encode", encoding::fn_url_encode);
+    env.add_function("url-decode", encoding::fn_url_decode);
+    env.add_function("sha256", encoding::fn_sha256);
+    env.add_function("sha1", encoding::fn_sha1);
+    env.add_function("md5", encoding::fn_md5);
+    env.add_function("crc32", encoding::fn_crc32);
+    env.add_function("uuid-v4", encoding::fn_uuid_v4);
+
+    // Formatting
+    env.add_function("sprintf", format::fn_sprintf);
+    env.add_function("printf", format::fn_printf);
+    env.add_function("pad-left", format::fn_pad_left);
+    env.add_function("pad-right", format::fn_pad_right);
+    env.add_function("center", format::fn_center);
+
     // I/O
     env.add_function("format", io::fn_format);
     env.add_function("write", io::fn_write);
@@ -221,6 +272,30 @@ fn create_environment(env: &mut Environment) {
     env.add_function("read-file", io::fn_read_file);
     env.add_function("ls", io::fn_ls);
     env.add_function("directory?", io::fn_is_directory);
+    env.add_function("append-file", io::fn_append_file);
+    env.add_function("file-exists?", io::fn_file_exists);
+    env.add_function("file-size", io::fn_file_size);
+    env.add_function("file-mtime", io::fn_file_mtime);
+    env.add_function("copy-file", io::fn_copy_file);
+    env.add_function("move-file", io::fn_move_file);
+    env.add_function("delete-file", io::fn_delete_file);
+    env.add_function("mkdir", io::fn_mkdir);
+    env.add_function("rmdir", io::fn_rmdir);
+    env.add_function("walk-dir", io::fn_walk_dir);
+    env.add_function("glob", io::fn_glob);
+    env.add_function("temp-file", io::fn_temp_file);
+    env.add_function("temp-dir", io::fn_temp_dir);
+    env.add_function("file-info", io::fn_file_info);
+    env.add_function("open", file::fn_open);
+    env.add_function("read-chars", file::fn_read_chars);
+    env.add_function("flush", file::fn_flush);
+    env.add_function("close", file::fn_close);
+    env.add_function("eof?", file::fn_is_eof);
+
+    // Lazy Sequences
+    env.add_function("lazy-range", lazy::fn_lazy_range);
+    env.add_function("lines-of-file", lazy::fn_lines_of_file);
+    env.add_function("realize", lazy::fn_realize);
 
     // List Manipulation
     env.add_function("car", list::fn_car);
@@ -231,11 +306,58 @@ fn create_environment(env: &mut Environment) {
     env.add_function("list", list::fn_list);
     env.add_function("last", list::fn_last);
     env.add_function("nth", list::fn_nth);
+    env.add_function("reverse", list::fn_reverse);
+    env.add_function("binary-search", list::fn_binary_search);
+    env.add_function("unique", list::fn_unique);
+    env.add_function("dedupe", list::fn_dedupe);
+    env.add_function("get", list::fn_get);
+
+    // Math
+    env.insert("pi", Node::Float(std::f64::consts::PI));
+    env.insert("e", Node::Float(std::f64::consts::E));
+    env.insert("inf", Node::Float(f64::INFINITY));
+    env.insert("nan", Node::Float(f64::NAN));
+    env.add_function("sin", math::fn_sin);
+    env.add_function("cos", math::fn_cos);
+    env.add_function("tan", math::fn_tan);
+    env.add_function("asin", math::fn_asin);
+    env.add_function("acos", math::fn_acos);
+    env.add_function("atan", math::fn_atan);
+    env.add_function("atan2", math::fn_atan2);
+    env.add_function("exp", math::fn_exp);
+    env.add_function("ln", math::fn_ln);
+    env.add_function("log10", math::fn_log10);
+    env.add_function("log2", math::fn_log2);
+    env.add_function("hypot", math::fn_hypot);
+    env.add_function("gcd", math::fn_gcd);
+    env.add_function("lcm", math::fn_lcm);
+    env.add_function("clamp", math::fn_clamp);
+    env.add_function("sign", math::fn_sign);
+    env.add_function("nan?", math::fn_is_nan);
+    env.add_function("infinite?", math::fn_is_infinite);
+    env.add_function("bit-and", math::fn_bit_and);
+    env.add_function("bit-or", math::fn_bit_or);
+    env.add_function("bit-xor", math::fn_bit_xor);
+    env.add_function("shift-left", math::fn_shift_left);
+    env.add_function("shift-right", math::fn_shift_right);
+    env.add_function("popcount", math::fn_popcount);
 
     // Parsing
     env.add_function("tokenize", parse::fn_tokenize);
     env.add_function("parse", parse::fn_parse);
 
+    // Paths
+    env.add_function("path-join", path::fn_path_join);
+    env.add_function("path-basename", path::fn_path_basename);
+    env.add_function("path-dirname", path::fn_path_dirname);
+    env.add_function("path-extension", path::fn_path_extension);
+    env.add_function("path-stem", path::fn_path_stem);
+    env.add_function("path-absolute", path::fn_path_absolute);
+    env.add_function("path-canonicalize", path::fn_path_canonicalize);
+    env.add_function("path-relative-to", path::fn_path_relative_to);
+    env.add_function("path-components", path::fn_path_components);
+    env.add_function("path-with-extension", path::fn_path_with_extension);
+
     // Random
     env.add_function("random-number", random::fn_random_number);
     env.add_function("random-letter", random::fn_random_letter);
@@ -243,6 +365,14 @@ fn create_environment(env: &mut Environment) {
     env.add_function("random-choice", random::fn_random_choice);
     env.add_function("random-boolean", random::fn_random_boolean);
     env.add_function("random-float", random::fn_random_float);
+    env.add_function("shuffle", random::fn_shuffle);
+    env.add_function("sample", random::fn_sample);
+    env.add_function("weighted-choice", random::fn_weighted_choice);
+    env.add_function("random-normal", random::fn_random_normal);
+    env.add_function("random-exponential", random::fn_random_exponential);
+    env.add_function("random-poisson", random::fn_random_poisson);
+    env.add_function("random-seed", random::fn_random_seed);
+    env.add_function("make-rng", random::fn_make_rng);
 
     // Regex
     env.add_function("regex", regex::fn_regex);
@@ -250,9 +380,36 @@ fn create_environment(env: &mut Environment) {
     env.add_function("regex-replace", regex::fn_regex_replace);
     env.add_function("regex-split", regex::fn_regex_split);
 
+    // Scheduling
+    env.add_function("cancel", scheduler::fn_cancel);
+    env.add_function("cron-next", scheduler::fn_cron_next);
+
     // Sequence Manipulation
     env.add_function("zip", sequence::fn_zip);
     env.add_function("range", sequence::fn_range);
+    env.add_function("take", sequence::fn_take);
+    env.add_function("drop", sequence::fn_drop);
+    env.add_function("chunk", sequence::fn_chunk);
+    env.add_function("window", sequence::fn_window);
+    env.add_function("flatten", sequence::fn_flatten);
+    env.add_function("interleave", sequence::fn_interleave);
+    env.add_function("enumerate", sequence::fn_enumerate);
+    env.add_function("frequencies", sequence::fn_frequencies);
+    env.add_function("append", sequence::fn_append);
+    env.add_function("repeat", sequence::fn_repeat);
+
+    // Statistics
+    env.add_function("sum", stats::fn_sum);
+    env.add_function("mean", stats::fn_mean);
+    env.add_function("median", stats::fn_median);
+    env.add_function("mode", stats::fn_mode);
+    env.add_function("variance", stats::fn_variance);
+    env.add_function("stddev", stats::fn_stddev);
+    env.add_function("percentile", stats::fn_percentile);
+    env.add_function("histogram", stats::fn_histogram);
+    env.add_function("min", stats::fn_min);
+    env.add_function("max", stats::fn_max);
+    env.add_function("cumulative-sum", stats::fn_cumulative_sum);
 
     // String Manipulation
     env.add_function("concat", string::fn_concat);
@@ -266,27 +423,87 @@ fn create_environment(env: &mut Environment) {
     env.add_function("lower", string::fn_lower);
     env.add_function("starts-with?", string::fn_starts_with);
     env.add_function("ends-with?", string::fn_ends_with);
+    env.add_function("string-length", string::fn_string_length);
+    env.add_function("char-count", string::fn_char_count);
+    env.add_function("byte-length", string::fn_byte_length);
+    env.add_function("string-ref", string::fn_string_ref);
+    env.add_function("nfc", string::fn_nfc);
+    env.add_function("nfd", string::fn_nfd);
+    env.add_function("fold-case", string::fn_fold_case);
+    env.add_function("display-width", string::fn_display_width);
 
     // System
     env.add_function("system", system::fn_system);
+    env.add_function("run", system::fn_run);
+    env.add_function("run!", system::fn_run_checked);
     env.add_function("version", system::fn_version);
     env.add_function("exit", system::fn_exit);
+    env.add_function("getenv", system::fn_getenv);
+    env.add_function("setenv", system::fn_setenv);
+    env.add_function("unsetenv", system::fn_unsetenv);
+    env.add_function("environ", system::fn_environ);
+    env.add_function("cwd", system::fn_cwd);
+    env.add_function("chdir", system::fn_chdir);
+    env.add_function("pid", system::fn_pid);
+    env.add_function("hostname", system::fn_hostname);
+    env.add_function("username", system::fn_username);
 
     // Terminal
     env.add_function("clear", terminal::fn_clear);
     env.add_function("alternate-screen", terminal::fn_alternate_screen);
-    env.add_function("normal-screen", terminal::fn_alternate_screen);
+    env.add_function("normal-screen", terminal::fn_normal_screen);
     env.add_function("fg", terminal::fn_fg);
     env.add_function("bg", terminal::fn_bg);
+    env.add_function("rgb", terminal::fn_rgb);
+    env.add_function("styled", terminal::fn_styled);
+    env.add_function("strip-ansi", terminal::fn_strip_ansi);
+    env.add_function("set-color-mode", terminal::fn_set_color_mode);
     env.add_function("set-cursor-pos", terminal::fn_set_cursor_pos);
+    env.add_function("enter-raw-mode", terminal::fn_enter_raw_mode);
+    env.add_function("leave-raw-mode", terminal::fn_leave_raw_mode);
+    env.add_function("hide-cursor", terminal::fn_hide_cursor);
+    env.add_function("show-cursor", terminal::fn_show_cursor);
+    env.add_function("terminal-size", terminal::fn_terminal_size);
+    env.add_function("read-key", terminal::fn_read_key);
+    env.add_function("screen-resize", terminal::fn_screen_resize);
+    env.add_function("screen-clear", terminal::fn_screen_clear);
+    env.add_function("screen-line", terminal::fn_screen_line);
+    env.add_function("screen-present", terminal::fn_screen_present);
+    env.add_function("draw-text", terminal::fn_draw_text);
+    env.add_function("draw-box", terminal::fn_draw_box);
 
     // Time
     env.add_function("time", time::fn_time);
     env.add_function("now", time::fn_now);
+    env.add_function("to-timezone", time::fn_to_timezone);
+    env.add_function("sleep", time::fn_sleep);
+    env.add_function("monotonic-ns", time::fn_monotonic_ns);
     env.add_function("add-days", time::fn_add_days);
     env.add_function("add-hours", time::fn_add_hours);
     env.add_function("add-minutes", time::fn_add_minutes);
     env.add_function("add-seconds", time::fn_add_seconds);
+    env.add_function("add-months", time::fn_add_months);
+    env.add_function("add-years", time::fn_add_years);
+    env.add_function("seconds", time::fn_seconds);
+    env.add_function("minutes", time::fn_minutes);
+    env.add_function("hours", time::fn_hours);
+    env.add_function("days", time::fn_days);
+    env.add_function("duration", time::fn_duration);
+    env.add_function("duration->seconds", time::fn_duration_to_seconds);
+    env.add_function("time-diff", time::fn_time_diff);
+    env.add_function("time+", time::fn_time_add);
+    env.add_function("time-", time::fn_time_subtract);
+    env.add_function("parse-time", time::fn_parse_time);
+    env.add_function("format-time", time::fn_format_time);
+    env.add_function("time-year", time::fn_time_year);
+    env.add_function("time-month", time::fn_time_month);
+    env.add_function("time-day", time::fn_time_day);
+    env.add_function("time-hour", time::fn_time_hour);
+    env.add_function("time-minute", time::fn_time_minute);
+    env.add_function("time-second", time::fn_time_second);
+    env.add_function("time-weekday", time::fn_time_weekday);
+    env.add_function("time-day-of-year", time::fn_time_day_of_year);
+    env.add_function("time-iso-week", time::fn_time_iso_week);
 
     // Tree Manipulation
     env.add_function("leaves", tree::fn_leaves);
@@ -307,9 +524,11 @@ fn usage() {
     println!("  -v, --verbose    Enable verbose mode");
     println!("  -V, --version    Show version information");
     println!("  -s, --server     Start in server mode");
+    println!("  --seed=N         Seed the random number generator");
 }
 
 fn main() {
+    let _terminal = terminal::RestoreGuard;
     let mut env = Environment::new();
 
     create_environment(&mut env);
@@ -329,6 +548,18 @@ fn main() {
     let verbose_flag = get_flag!(flag_args, "-v", "--verbose");
     let version_flag = get_flag!(flag_args, "-V", "--version");
 
+    for arg in &flag_args {
+        if let Some(seed) = arg.strip_prefix("--seed=") {
+            match seed.parse::<u64>() {
+                Ok(seed) => random::seed(seed),
+                Err(_) => {
+                    eprintln!("Invalid seed: {seed}");
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+
     if version_flag {
         print_version(&mut env);
         return;
@@ -0,0 +1,258 @@
+use crate::invalid_arguments;
+use crate::node::Node;
+
+#[allow(clippy::cast_precision_loss)]
+pub fn to_float(node: &Node) -> Option<f64> {
+    match node {
+        Node::Number(n) => Some(*n as f64),
+        Node::Float(f) => Some(*f),
+        _ => None,
+    }
+}
+
+macro_rules! unary_float_fn {
+    ($fn_name:ident, $name:expr, $op:expr) => {
+        pub fn $fn_name(arguments: &[Node]) -> Result<Node, String> {
+            match arguments {
+                [x] => match to_float(x) {
+                    Some(x) => Ok(Node::Float($op(x))),
+                    None => invalid_arguments!($name, arguments, ["[Number(x)]", "[Float(x)]"]),
+                },
+                _ => invalid_arguments!($name, arguments, ["[Number(x)]", "[Float(x)]"]),
+            }
+        }
+    };
+}
+
+//- (test "sin" (sin 0.0) 0.0)
+//- (test "sin" (sin 0) 0.0)
+unary_float_fn!(fn_sin, "sin", f64::sin);
+
+//- (test "cos" (cos 0.0) 1.0)
+//- (test "cos" (cos 0) 1.0)
+unary_float_fn!(fn_cos, "cos", f64::cos);
+
+//- (test "tan" (tan 0.0) 0.0)
+unary_float_fn!(fn_tan, "tan", f64::tan);
+
+//- (test "asin" (asin 0.0) 0.0)
+unary_float_fn!(fn_asin, "asin", f64::asin);
+
+//- (test "acos" (acos 1.0) 0.0)
+unary_float_fn!(fn_acos, "acos", f64::acos);
+
+//- (test "atan" (atan 0.0) 0.0)
+unary_float_fn!(fn_atan, "atan", f64::atan);
+
+//- (test "exp" (exp 0) 1.0)
+//- (test "exp" (exp 0.0) 1.0)
+unary_float_fn!(fn_exp, "exp", f64::exp);
+
+//- (test "ln" (ln 1) 0.0)
+//- (test "ln" (ln e) 1.0)
+unary_float_fn!(fn_ln, "ln", f64::ln);
+
+//- (test "log10" (log10 1000) 3.0)
+//- (test "log10" (log10 1.0) 0.0)
+unary_float_fn!(fn_log10, "log10", f64::log10);
+
+//- (test "log2" (log2 8) 3.0)
+//- (test "log2" (log2 1.0) 0.0)
+unary_float_fn!(fn_log2, "log2", f64::log2);
+
+//- (test "atan2" (atan2 0.0 1.0) 0.0)
+//- (test "atan2" (atan2 0 -1) pi)
+pub fn fn_atan2(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [y, x] => match (to_float(y), to_float(x)) {
+            (Some(y), Some(x)) => Ok(Node::Float(y.atan2(x))),
+            _ => invalid_arguments!("atan2", arguments, ["[Number|Float(y), Number|Float(x)]"]),
+        },
+        _ => invalid_arguments!("atan2", arguments, ["[Number|Float(y), Number|Float(x)]"]),
+    }
+}
+
+//- (test "hypot" (hypot 3 4) 5.0)
+//- (test "hypot" (hypot 3.0 4.0) 5.0)
+pub fn fn_hypot(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [x, y] => match (to_float(x), to_float(y)) {
+            (Some(x), Some(y)) => Ok(Node::Float(x.hypot(y))),
+            _ => invalid_arguments!("hypot", arguments, ["[Number|Float(x), Number|Float(y)]"]),
+        },
+        _ => invalid_arguments!("hypot", arguments, ["[Number|Float(x), Number|Float(y)]"]),
+    }
+}
+
+fn gcd(a: i64, b: i64) -> u64 {
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+//- (test "gcd" (gcd 12 18) 6)
+//- (test "gcd" (gcd -12 18) 6)
+//- (test "gcd" (gcd 0 5) 5)
+//- (test "gcd" (gcd -9223372036854775808 6) 2)
+pub fn fn_gcd(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Number(a), Node::Number(b)] => i64::try_from(gcd(*a, *b))
+            .map(Node::Number)
+            .map_err(|_| format!("Overflow computing gcd of {a} and {b}")),
+        _ => invalid_arguments!("gcd", arguments, ["[Number(a), Number(b)]"]),
+    }
+}
+
+//- (test "lcm" (lcm 4 6) 12)
+//- (test "lcm" (lcm -4 6) 12)
+//- (test "lcm" (lcm 0 6) 0)
+pub fn fn_lcm(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Number(a), Node::Number(b)] => {
+            if *a == 0 || *b == 0 {
+                return Ok(Node::Number(0));
+            }
+            i64::try_from(gcd(*a, *b))
+                .ok()
+                .and_then(|gcd| (a / gcd).checked_mul(*b))
+                .and_then(i64::checked_abs)
+                .map(Node::Number)
+                .ok_or_else(|| format!("Overflow computing lcm of {a} and {b}"))
+        }
+        _ => invalid_arguments!("lcm", arguments, ["[Number(a), Number(b)]"]),
+    }
+}
+
+//- (test "clamp" (clamp 5 0 10) 5)
+//- (test "clamp" (clamp -5 0 10) 0)
+//- (test "clamp" (clamp 15.0 0.0 10.0) 10.0)
+pub fn fn_clamp(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Number(x), Node::Number(min), Node::Number(max)] => {
+            if min > max {
+                return Err("Minimum value cannot be greater than maximum value".to_string());
+            }
+            Ok(Node::Number((*x).clamp(*min, *max)))
+        }
+        [Node::Float(x), Node::Float(min), Node::Float(max)] => {
+            if min > max {
+                return Err("Minimum value cannot be greater than maximum value".to_string());
+            }
+            Ok(Node::Float(x.clamp(*min, *max)))
+        }
+        _ => invalid_arguments!(
+            "clamp",
+            arguments,
+            [
+                "[Number(x), Number(min), Number(max)]",
+                "[Float(x), Float(min), Float(max)]"
+            ]
+        ),
+    }
+}
+
+//- (test "sign" (sign -7) -1)
+//- (test "sign" (sign 0) 0)
+//- (test "sign" (sign 2.5) 1.0)
+pub fn fn_sign(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Number(n)] => Ok(Node::Number(n.signum())),
+        [Node::Float(f)] => {
+            if *f == 0.0 || f.is_nan() {
+                Ok(Node::Float(*f))
+            } else {
+                Ok(Node::Float(f.signum()))
+            }
+        }
+        _ => invalid_arguments!("sign", arguments, ["[Number(n)]", "[Float(f)]"]),
+    }
+}
+
+//- (test "nan?" (nan? nan) true)
+//- (test "nan?" (nan? 1.0) false)
+//- (test "nan?" (nan? 1) false)
+pub fn fn_is_nan(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Number(_)] => Ok(Node::Bool(false)),
+        [Node::Float(f)] => Ok(Node::Bool(f.is_nan())),
+        _ => invalid_arguments!("nan?", arguments, ["[Number(n)]", "[Float(f)]"]),
+    }
+}
+
+//- (test "infinite?" (infinite? inf) true)
+//- (test "infinite?" (infinite? (- 0.0 inf)) true)
+//- (test "infinite?" (infinite? 1.0) false)
+pub fn fn_is_infinite(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Number(_)] => Ok(Node::Bool(false)),
+        [Node::Float(f)] => Ok(Node::Bool(f.is_infinite())),
+        _ => invalid_arguments!("infinite?", arguments, ["[Number(n)]", "[Float(f)]"]),
+    }
+}
+
+//- (test "bit-and" (bit-and 12 10) 8)
+//- (test "bit-and" (bit-and -1 5) 5)
+pub fn fn_bit_and(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Number(a), Node::Number(b)] => Ok(Node::Number(a & b)),
+        _ => invalid_arguments!("bit-and", arguments, ["[Number(a), Number(b)]"]),
+    }
+}
+
+//- (test "bit-or" (bit-or 12 10) 14)
+//- (test "bit-or" (bit-or 0 0) 0)
+pub fn fn_bit_or(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Number(a), Node::Number(b)] => Ok(Node::Number(a | b)),
+        _ => invalid_arguments!("bit-or", arguments, ["[Number(a), Number(b)]"]),
+    }
+}
+
+//- (test "bit-xor" (bit-xor 12 10) 6)
+//- (test "bit-xor" (bit-xor 5 5) 0)
+pub fn fn_bit_xor(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Number(a), Node::Number(b)] => Ok(Node::Number(a ^ b)),
+        _ => invalid_arguments!("bit-xor", arguments, ["[Number(a), Number(b)]"]),
+    }
+}
+
+//- (test "shift-left" (shift-left 1 4) 16)
+//- (test "shift-left" (shift-left 3 0) 3)
+pub fn fn_shift_left(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Number(n), Node::Number(shift)] => {
+            let shift = u32::try_from(*shift).map_err(|_| format!("Invalid shift: {shift}"))?;
+            n.checked_shl(shift)
+                .map(Node::Number)
+                .ok_or_else(|| format!("Shift amount too large: {shift}"))
+        }
+        _ => invalid_arguments!("shift-left", arguments, ["[Number(n), Number(shift)]"]),
+    }
+}
+
+//- (test "shift-right" (shift-right 16 4) 1)
+//- (test "shift-right" (shift-right -16 2) -4)
+pub fn fn_shift_right(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Number(n), Node::Number(shift)] => {
+            let shift = u32::try_from(*shift).map_err(|_| format!("Invalid shift: {shift}"))?;
+            n.checked_shr(shift)
+                .map(Node::Number)
+                .ok_or_else(|| format!("Shift amount too large: {shift}"))
+        }
+        _ => invalid_arguments!("shift-right", arguments, ["[Number(n), Number(shift)]"]),
+    }
+}
+
+//- (test "popcount" (popcount 7) 3)
+//- (test "popcount" (popcount 0) 0)
+//- (test "popcount" (popcount -1) 64)
+pub fn fn_popcount(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Number(n)] => Ok(Node::Number(i64::from(n.count_ones()))),
+        _ => invalid_arguments!("popcount", arguments, ["[Number(n)]"]),
+    }
+}
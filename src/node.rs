@@ -1,4 +1,7 @@
-use chrono::TimeZone;
+use crate::file::FileHandle;
+use crate::lazy::Lazy;
+use std::cell::RefCell;
+use std::rc::Rc;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Node {
@@ -7,10 +10,17 @@ pub enum Node {
     Float(f64),
     Text(String),
     Bool(bool),
+    Char(char),
     List(Vec<Node>),
-    Time(i64, i32), // Seconds since epoch and timezone offset in seconds
+    Time(i64, i32), // UTC seconds since epoch and timezone offset east of UTC in seconds
+    Duration(i64),  // Seconds
+    Job(u64),
     Function(fn(&[Node]) -> Result<Node, String>),
     Regex(String), // TODO: It would be more efficient to store a compiled regex
+    Lazy(Rc<Lazy>),
+    File(Rc<RefCell<FileHandle>>),
+    Bytes(Vec<u8>),
+    Rng(Rc<RefCell<rand::rngs::StdRng>>),
 }
 
 impl std::fmt::Display for Node {
@@ -18,17 +28,19 @@ impl std::fmt::Display for Node {
         let res = match self {
             Self::Number(n) => n.to_string(),
             Self::Bool(b) => b.to_string(),
+            Self::Char(c) => c.to_string(),
             Self::Float(f) => f.to_string(),
             Self::Time(t, z) => {
-                let local_time = chrono::Utc
-                    .timestamp_opt(*t, 0)
-                    .single()
-                    .expect("Invalid timestamp");
+                let Ok(local_time) = crate::time::to_datetime(*t, *z) else {
+                    return write!(f, "time({t}, {z})");
+                };
                 let local_time_str = local_time.format("%Y-%m-%d %H:%M:%S").to_string();
-                let offset_hours = z / 3600;
-                let offset_minutes = (z % 3600) / 60;
-                format!("{local_time_str} UTC{offset_hours:+03}:{offset_minutes:02}")
+                let sign = if *z < 0 { '-' } else { '+' };
+                let offset_hours = z.abs() / 3600;
+                let offset_minutes = (z.abs() % 3600) / 60;
+                format!("{local_time_str} UTC{sign}{offset_hours:02}:{offset_minutes:02}")
             }
+            Self::Duration(seconds) => crate::time::format_duration(*seconds),
             Self::Text(s) | Self::Symbol(s) => s.clone(),
             Self::Function(_) => "function".to_string(),
             Self::List(nodes) => {
@@ -45,6 +57,21 @@ impl std::fmt::Display for Node {
                 result
             }
             Self::Regex(r) => format!("regex({r})"),
+            Self::Lazy(seq) => seq.preview(),
+            Self::File(handle) => match handle.try_borrow() {
+                Ok(handle) => format!("file({})", handle.path()),
+                Err(_) => "file".to_string(),
+            },
+            Self::Rng(_) => "rng".to_string(),
+            Self::Job(id) => format!("job({id})"),
+            Self::Bytes(bytes) => format!(
+                "bytes({})",
+                bytes
+                    .iter()
+                    .map(|b| format!("{b:02x}"))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
         };
 
         if res.is_empty() {
@@ -6,6 +6,7 @@ enum Token {
     Float(f64),
     Text(String),
     Bool(bool),
+    Char(char),
     LParen,
     RParen,
 }
@@ -18,6 +19,7 @@ impl std::fmt::Display for Token {
             Self::Float(x) => write!(f, "Float({x})"),
             Self::Text(s) => write!(f, "Text({s})"),
             Self::Bool(b) => write!(f, "Bool({b})"),
+            Self::Char(c) => write!(f, "Char({c})"),
             Self::LParen => write!(f, "("),
             Self::RParen => write!(f, ")"),
         }
@@ -77,6 +79,7 @@ pub fn parse(input: &str) -> Result<Vec<Node>, String> {
             Token::Float(x) => current_list.push(Node::Float(x)),
             Token::Text(s) => current_list.push(Node::Text(s.clone())),
             Token::Bool(b) => current_list.push(Node::Bool(b)),
+            Token::Char(c) => current_list.push(Node::Char(c)),
         }
     }
 
@@ -87,16 +90,13 @@ fn tokenize(source: &str) -> Result<Vec<Token>, String> {
     let mut tokens = Vec::new();
     let mut chars = source.char_indices().peekable();
 
-    if let Some((_, '#')) = chars.peek() {
-        chars.next();
-        if let Some((_, '!')) = chars.peek() {
-            chars.next();
-            while let Some(&(_, c)) = chars.peek() {
-                if c == '\n' {
-                    break;
-                }
-                chars.next();
+    // A shebang line, as opposed to a leading character literal like #\a
+    if source.starts_with("#!") {
+        while let Some(&(_, c)) = chars.peek() {
+            if c == '\n' {
+                break;
             }
+            chars.next();
         }
     }
 
@@ -147,6 +147,25 @@ fn tokenize(source: &str) -> Result<Vec<Token>, String> {
                 }
                 tokens.push(Token::Text(value.replace("\\n", "\n")));
             }
+            '#' if chars.peek().is_some_and(|&(_, next_c)| next_c == '\\') => {
+                chars.next();
+                let (_, first) = chars
+                    .next()
+                    .ok_or_else(|| "Missing character after #\\".to_string())?;
+                let mut name = String::from(first);
+                // A letter or digit may start a name such as #\newline or #\x41
+                if first.is_alphanumeric() {
+                    while let Some(&(_, next_c)) = chars.peek() {
+                        if next_c.is_alphanumeric() {
+                            name.push(next_c);
+                            chars.next();
+                        } else {
+                            break;
+                        }
+                    }
+                }
+                tokens.push(Token::Char(crate::character::from_name(&name)?));
+            }
             c if is_symbol_char!(c) => {
                 let mut value = String::from(c);
                 while let Some(&(_, next_c)) = chars.peek() {
@@ -0,0 +1,185 @@
+use crate::invalid_arguments;
+use crate::node::Node;
+use std::path::{Component, Path, PathBuf};
+
+fn to_text(path: &Path) -> Node {
+    Node::Text(path.to_string_lossy().to_string())
+}
+
+//- (test "path-join" (path-join "foo" "bar" "baz.txt") "foo/bar/baz.txt")
+//- (test "path-join" (path-join "foo/" "bar") "foo/bar")
+//- (test "path-join" (path-join "foo" "/bar") "/bar")
+pub fn fn_path_join(arguments: &[Node]) -> Result<Node, String> {
+    let mut path = PathBuf::new();
+    for argument in arguments {
+        match argument {
+            Node::Text(part) => path.push(part),
+            _ => return invalid_arguments!("path-join", arguments, ["[Text(path), ...]"]),
+        }
+    }
+    Ok(to_text(&path))
+}
+
+//- (test "path-basename" (path-basename "src/main.rs") "main.rs")
+//- (test "path-basename" (path-basename "/usr/lib/") "lib")
+//- (test "path-basename" (path-basename "/") "")
+pub fn fn_path_basename(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(path)] => Ok(Node::Text(
+            Path::new(path)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+        )),
+        _ => invalid_arguments!("path-basename", arguments, ["[Text(path)]"]),
+    }
+}
+
+//- (test "path-dirname" (path-dirname "src/main.rs") "src")
+//- (test "path-dirname" (path-dirname "main.rs") ".")
+//- (test "path-dirname" (path-dirname "/") "/")
+pub fn fn_path_dirname(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(path)] => Ok(match Path::new(path).parent() {
+            Some(parent) if parent.as_os_str().is_empty() => Node::Text(".".to_string()),
+            Some(parent) => to_text(parent),
+            None => Node::Text(path.clone()),
+        }),
+        _ => invalid_arguments!("path-dirname", arguments, ["[Text(path)]"]),
+    }
+}
+
+//- (test "path-extension" (path-extension "src/main.rs") "rs")
+//- (test "path-extension" (path-extension "archive.tar.gz") "gz")
+//- (test "path-extension" (path-extension "Makefile") "")
+pub fn fn_path_extension(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(path)] => Ok(Node::Text(
+            Path::new(path)
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_string())
+                .unwrap_or_default(),
+        )),
+        _ => invalid_arguments!("path-extension", arguments, ["[Text(path)]"]),
+    }
+}
+
+//- (test "path-stem" (path-stem "src/main.rs") "main")
+//- (test "path-stem" (path-stem "archive.tar.gz") "archive.tar")
+//- (test "path-stem" (path-stem ".bashrc") ".bashrc")
+pub fn fn_path_stem(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(path)] => Ok(Node::Text(
+            Path::new(path)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default(),
+        )),
+        _ => invalid_arguments!("path-stem", arguments, ["[Text(path)]"]),
+    }
+}
+
+//- (test "path-absolute" (path-absolute "/usr/lib") "/usr/lib")
+//- (test "path-absolute" (path-absolute "src") (path-join (cwd) "src"))
+pub fn fn_path_absolute(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(path)] => std::path::absolute(path)
+            .map(|absolute| to_text(&absolute))
+            .map_err(|e| format!("Failed to make path absolute: {path}: {e}")),
+        _ => invalid_arguments!("path-absolute", arguments, ["[Text(path)]"]),
+    }
+}
+
+//- (test "path-canonicalize" (path-canonicalize "src/../src/main.rs") (path-join (cwd) "src/main.rs"))
+pub fn fn_path_canonicalize(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(path)] => std::fs::canonicalize(path)
+            .map(|canonical| to_text(&canonical))
+            .map_err(|e| format!("Failed to canonicalize path: {path}: {e}")),
+        _ => invalid_arguments!("path-canonicalize", arguments, ["[Text(path)]"]),
+    }
+}
+
+//- (test "path-relative-to" (path-relative-to "/usr" "/usr/lib/x.so") "lib/x.so")
+//- (test "path-relative-to" (path-relative-to "/usr/share" "/usr/lib") "../lib")
+//- (test "path-relative-to" (path-relative-to "src" "src") ".")
+pub fn fn_path_relative_to(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(base), Node::Text(path)] => {
+            let base_path = Path::new(base);
+            let target = Path::new(path);
+            if base_path.is_absolute() != target.is_absolute() {
+                return Err(format!(
+                    "Cannot relate '{path}' to '{base}': both paths must be absolute or both relative"
+                ));
+            }
+
+            let base_components = base_path
+                .components()
+                .filter(|c| *c != Component::CurDir)
+                .collect::<Vec<_>>();
+            let target_components = target
+                .components()
+                .filter(|c| *c != Component::CurDir)
+                .collect::<Vec<_>>();
+            let common = base_components
+                .iter()
+                .zip(&target_components)
+                .take_while(|(a, b)| a == b)
+                .count();
+
+            let mut relative = PathBuf::new();
+            for component in &base_components[common..] {
+                if *component == Component::ParentDir {
+                    return Err(format!(
+                        "Cannot relate '{path}' to '{base}': base escapes through '..'"
+                    ));
+                }
+                relative.push("..");
+            }
+            for component in &target_components[common..] {
+                relative.push(component);
+            }
+            if relative.as_os_str().is_empty() {
+                relative.push(".");
+            }
+            Ok(to_text(&relative))
+        }
+        _ => invalid_arguments!("path-relative-to", arguments, ["[Text(base), Text(path)]"]),
+    }
+}
+
+//- (test "path-components" (path-components "/usr/lib/x.so") (quote ("/" "usr" "lib" "x.so")))
+//- (test "path-components" (path-components "src/main.rs") (quote ("src" "main.rs")))
+pub fn fn_path_components(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(path)] => Ok(Node::List(
+            Path::new(path)
+                .components()
+                .map(|component| Node::Text(component.as_os_str().to_string_lossy().to_string()))
+                .collect(),
+        )),
+        _ => invalid_arguments!("path-components", arguments, ["[Text(path)]"]),
+    }
+}
+
+//- (test "path-with-extension" (path-with-extension "md" "docs/readme.txt") "docs/readme.md")
+//- (test "path-with-extension" (path-with-extension "gz" "archive.tar") "archive.gz")
+//- (test "path-with-extension" (path-with-extension "" "main.rs") "main")
+pub fn fn_path_with_extension(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(extension), Node::Text(path)] => {
+            if extension.contains(['/', std::path::MAIN_SEPARATOR]) {
+                return Err(format!(
+                    "Extension cannot contain a path separator: {extension}"
+                ));
+            }
+            Ok(to_text(&Path::new(path).with_extension(extension)))
+        }
+        _ => invalid_arguments!(
+            "path-with-extension",
+            arguments,
+            ["[Text(extension), Text(path)]"]
+        ),
+    }
+}
@@ -1,17 +1,90 @@
 use crate::invalid_arguments;
+use crate::math::to_float;
 use crate::node::Node;
-use rand::Rng;
-use rand::prelude::IteratorRandom;
+use rand::rngs::StdRng;
+use rand::seq::{IndexedRandom, SliceRandom};
+use rand::{Rng, SeedableRng};
+use rand_distr::{Distribution, Exp, Normal, Poisson, weighted::WeightedIndex};
+use std::cell::RefCell;
+use std::rc::Rc;
 
-pub fn fn_random_number(arguments: &[Node]) -> Result<Node, String> {
+const LETTERS: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const ALPHANUMERICS: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+
+thread_local! {
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_os_rng());
+}
+
+/// Reseed the interpreter-wide generator used when no RNG object is passed.
+pub fn seed(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
+}
+
+/// Run `f` with the generator selected by `arguments`: a leading `Rng` object
+/// if present (which is stripped from the arguments), otherwise the
+/// interpreter-wide generator.
+pub fn with_rng<T>(
+    arguments: &[Node],
+    f: impl FnOnce(&mut StdRng, &[Node]) -> Result<T, String>,
+) -> Result<T, String> {
+    match arguments {
+        [Node::Rng(rng), rest @ ..] => f(&mut rng.borrow_mut(), rest),
+        _ => RNG.with(|rng| f(&mut rng.borrow_mut(), arguments)),
+    }
+}
+
+fn random_text(rng: &mut StdRng, length: i64, alphabet: &str) -> Result<Node, String> {
+    if length < 0 {
+        return Err("Length cannot be negative".to_string());
+    }
+    if alphabet.is_empty() {
+        return Err("Alphabet cannot be empty".to_string());
+    }
+    let alphabet = alphabet.chars().collect::<Vec<_>>();
+    Ok(Node::Text(
+        (0..length)
+            .map(|_| alphabet[rng.random_range(0..alphabet.len())])
+            .collect(),
+    ))
+}
+
+fn parameter(name: &str, node: &Node) -> Result<f64, String> {
+    to_float(node).ok_or_else(|| format!("{name} must be a number, found {node:?}"))
+}
+
+//- (test "random-seed" (begin (random-seed 42) (let ((a (random-number 1000))) (begin (random-seed 42) (= a (random-number 1000))))) true)
+pub fn fn_random_seed(arguments: &[Node]) -> Result<Node, String> {
     match arguments {
-        [] => Ok(Node::Number(rand::random::<i64>())),
+        [Node::Number(n)] => {
+            seed(n.cast_unsigned());
+            Ok(Node::Bool(true))
+        }
+        _ => invalid_arguments!("random-seed", arguments, ["[Number(seed)]"]),
+    }
+}
+
+//- (test "make-rng" (type? (make-rng 1)) "rng")
+//- (test "make-rng" (= (random-float (make-rng 7)) (random-float (make-rng 7))) true)
+//- (test "make-rng" (let ((r (make-rng 7))) (= (random-string r 8) (random-string r 8))) false)
+pub fn fn_make_rng(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [] => Ok(Node::Rng(Rc::new(RefCell::new(StdRng::from_os_rng())))),
+        [Node::Number(n)] => Ok(Node::Rng(Rc::new(RefCell::new(StdRng::seed_from_u64(
+            n.cast_unsigned(),
+        ))))),
+        _ => invalid_arguments!("make-rng", arguments, ["[]", "[Number(seed)]"]),
+    }
+}
+
+pub fn fn_random_number(arguments: &[Node]) -> Result<Node, String> {
+    with_rng(arguments, |rng, arguments| match arguments {
+        [] => Ok(Node::Number(rng.random::<i64>())),
         [Node::Number(max)] => {
             if *max < 0 {
                 return Err("Maximum value cannot be negative".to_string());
             }
             let max = { *max };
-            let random_number = rand::rng().random_range(0..=max);
+            let random_number = rng.random_range(0..=max);
             Ok(Node::Number(random_number))
         }
         [Node::Number(min), Node::Number(max)] => {
@@ -20,7 +93,7 @@ pub fn fn_random_number(arguments: &[Node]) -> Result<Node, String> {
             }
             let min = { *min };
             let max = { *max };
-            let random_number = rand::rng().random_range(min..=max);
+            let random_number = rng.random_range(min..=max);
             Ok(Node::Number(random_number))
         }
         [Node::Float(max)] => {
@@ -28,7 +101,7 @@ pub fn fn_random_number(arguments: &[Node]) -> Result<Node, String> {
                 return Err("Maximum value cannot be negative".to_string());
             }
             let max = *max;
-            let random_number = rand::rng().random_range(0.0..=max);
+            let random_number = rng.random_range(0.0..=max);
             Ok(Node::Float(random_number))
         }
         [Node::Float(min), Node::Float(max)] => {
@@ -37,7 +110,7 @@ pub fn fn_random_number(arguments: &[Node]) -> Result<Node, String> {
             }
             let min = *min;
             let max = *max;
-            let random_number = rand::rng().random_range(min..=max);
+            let random_number = rng.random_range(min..=max);
             Ok(Node::Float(random_number))
         }
         _ => invalid_arguments!(
@@ -52,63 +125,169 @@ pub fn fn_random_number(arguments: &[Node]) -> Result<Node, String> {
                 "[Float(min), Float(max)]"
             ]
         ),
-    }
+    })
 }
 
+//- (test "random-letter" (length (random-letter)) 1)
+//- (test "random-letter" (length (random-letter 5)) 5)
 pub fn fn_random_letter(arguments: &[Node]) -> Result<Node, String> {
-    let letters = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
-    match arguments {
-        [] => Ok(Node::Text(
-            letters
-                .chars()
-                .choose(&mut rand::thread_rng())
-                .unwrap()
-                .to_string(),
-        )),
+    with_rng(arguments, |rng, arguments| match arguments {
+        [] => random_text(rng, 1, LETTERS),
+        [Node::Number(length)] => random_text(rng, *length, LETTERS),
         _ => invalid_arguments!("random-letter", arguments, ["[]", "[Number(length)]"]),
-    }
+    })
 }
 
+//- (test "random-string" (length (random-string 12)) 12)
+//- (test "random-string" (random-string 4 "x") "xxxx")
+//- (test "random-string" (length (random-string 3 "ab")) 3)
 pub fn fn_random_string(arguments: &[Node]) -> Result<Node, String> {
-    let letters = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
-    match arguments {
-        [Node::Number(length)] => {
-            if *length < 0 {
-                return Err("Length cannot be negative".to_string());
-            }
-            let length = *length;
-            let random_string: String = (0..length)
-                .map(|_| letters.chars().choose(&mut rand::thread_rng()).unwrap())
-                .collect();
-            Ok(Node::Text(random_string))
-        }
-        _ => invalid_arguments!("random-string", arguments, ["[Number(length)]"]),
-    }
+    with_rng(arguments, |rng, arguments| match arguments {
+        [Node::Number(length)] => random_text(rng, *length, ALPHANUMERICS),
+        [Node::Number(length), Node::Text(alphabet)] => random_text(rng, *length, alphabet),
+        _ => invalid_arguments!(
+            "random-string",
+            arguments,
+            ["[Number(length)]", "[Number(length), Text(alphabet)]"]
+        ),
+    })
 }
 
 pub fn fn_random_choice(arguments: &[Node]) -> Result<Node, String> {
-    match arguments {
+    with_rng(arguments, |rng, arguments| match arguments {
         [Node::List(list)] => {
             if list.is_empty() {
                 return Err("Cannot choose from an empty list".to_string());
             }
-            let random_index = rand::rng().random_range(0..list.len());
+            let random_index = rng.random_range(0..list.len());
             Ok(list[random_index].clone())
         }
         _ => invalid_arguments!("random-choice", arguments, ["[List(list)]"]),
-    }
+    })
 }
 
 pub fn fn_random_boolean(arguments: &[Node]) -> Result<Node, String> {
-    match arguments {
-        [] => Ok(Node::Bool(rand::random())),
+    with_rng(arguments, |rng, arguments| match arguments {
+        [] => Ok(Node::Bool(rng.random())),
         _ => invalid_arguments!("random-boolean", arguments, ["[]"]),
-    }
+    })
 }
 
 pub fn fn_random_float(arguments: &[Node]) -> Result<Node, String> {
-    match arguments {
-        [] => Ok(Node::Float(rand::random())),
+    with_rng(arguments, |rng, arguments| match arguments {
+        [] => Ok(Node::Float(rng.random())),
         _ => invalid_arguments!("random-float", arguments, ["[]"]),
-    }
+    })
+}
+
+//- (test "shuffle" (sort (shuffle (quote (3 1 2)))) (quote (1 2 3)))
+//- (test "shuffle" (shuffle (quote ())) (quote ()))
+pub fn fn_shuffle(arguments: &[Node]) -> Result<Node, String> {
+    with_rng(arguments, |rng, arguments| match arguments {
+        [Node::List(list)] => {
+            let mut list = list.clone();
+            list.shuffle(rng);
+            Ok(Node::List(list))
+        }
+        _ => invalid_arguments!("shuffle", arguments, ["[List(list)]"]),
+    })
+}
+
+//- (test "sample" (length (sample 2 (quote (1 2 3)))) 2)
+//- (test "sample" (sort (sample 3 (quote (1 2 3)))) (quote (1 2 3)))
+pub fn fn_sample(arguments: &[Node]) -> Result<Node, String> {
+    with_rng(arguments, |rng, arguments| match arguments {
+        [Node::Number(count), Node::List(list)] => {
+            let count = usize::try_from(*count)
+                .ok()
+                .filter(|count| *count <= list.len())
+                .ok_or_else(|| {
+                    format!(
+                        "Cannot sample {count} items from a list of length {}",
+                        list.len()
+                    )
+                })?;
+            Ok(Node::List(
+                list.choose_multiple(rng, count).cloned().collect(),
+            ))
+        }
+        _ => invalid_arguments!("sample", arguments, ["[Number(count), List(list)]"]),
+    })
+}
+
+//- (test "weighted-choice" (weighted-choice (quote (a b c)) (quote (0 1 0))) (quote b))
+//- (test "weighted-choice" (weighted-choice (quote (x)) (quote (0.5))) (quote x))
+pub fn fn_weighted_choice(arguments: &[Node]) -> Result<Node, String> {
+    with_rng(arguments, |rng, arguments| match arguments {
+        [Node::List(items), Node::List(weights)] => {
+            if items.len() != weights.len() {
+                return Err(format!(
+                    "Expected one weight per item, found {} items and {} weights",
+                    items.len(),
+                    weights.len()
+                ));
+            }
+            let weights = weights
+                .iter()
+                .map(|weight| parameter("Weight", weight))
+                .collect::<Result<Vec<_>, _>>()?;
+            let index =
+                WeightedIndex::new(&weights).map_err(|e| format!("Invalid weights: {e}"))?;
+            Ok(items[index.sample(rng)].clone())
+        }
+        _ => invalid_arguments!(
+            "weighted-choice",
+            arguments,
+            ["[List(items), List(weights)]"]
+        ),
+    })
+}
+
+//- (test "random-normal" (type? (random-normal)) "float")
+//- (test "random-normal" (random-normal 5 0) 5.0)
+pub fn fn_random_normal(arguments: &[Node]) -> Result<Node, String> {
+    with_rng(arguments, |rng, arguments| {
+        let (mean, stddev) = match arguments {
+            [] => (0.0, 1.0),
+            [mean, stddev] => (
+                parameter("Mean", mean)?,
+                parameter("Standard deviation", stddev)?,
+            ),
+            _ => {
+                return invalid_arguments!(
+                    "random-normal",
+                    arguments,
+                    ["[]", "[Float(mean), Float(stddev)]"]
+                );
+            }
+        };
+        let normal =
+            Normal::new(mean, stddev).map_err(|e| format!("Invalid normal distribution: {e}"))?;
+        Ok(Node::Float(normal.sample(rng)))
+    })
+}
+
+//- (test "random-exponential" (type? (random-exponential 2)) "float")
+pub fn fn_random_exponential(arguments: &[Node]) -> Result<Node, String> {
+    with_rng(arguments, |rng, arguments| match arguments {
+        [rate] => {
+            let exp = Exp::new(parameter("Rate", rate)?)
+                .map_err(|e| format!("Invalid exponential distribution: {e}"))?;
+            Ok(Node::Float(exp.sample(rng)))
+        }
+        _ => invalid_arguments!("random-exponential", arguments, ["[Float(rate)]"]),
+    })
+}
+
+//- (test "random-poisson" (type? (random-poisson 3)) "number")
+//- (test "random-poisson" (>= (random-poisson 0.5) 0) true)
+pub fn fn_random_poisson(arguments: &[Node]) -> Result<Node, String> {
+    with_rng(arguments, |rng, arguments| match arguments {
+        [lambda] => {
+            let poisson = Poisson::new(parameter("Lambda", lambda)?)
+                .map_err(|e| format!("Invalid Poisson distribution: {e}"))?;
+            Ok(Node::Number(poisson.sample(rng) as i64))
+        }
+        _ => invalid_arguments!("random-poisson", arguments, ["[Float(lambda)]"]),
+    })
 }
@@ -0,0 +1,353 @@
+use crate::environment::Environment;
+use crate::eval::{apply, eval};
+use crate::invalid_arguments;
+use crate::node::Node;
+use crate::time::{from_local, to_datetime};
+use chrono::{Datelike, Duration, NaiveDateTime, TimeZone, Timelike};
+use std::cell::{Cell, RefCell};
+use std::time::{Instant, SystemTime};
+
+// Searching further ahead than this means the expression can never match
+// (e.g. "0 0 31 2 *")
+const CRON_SEARCH_LIMIT: usize = 5 * 366 * 24 * 60;
+
+struct Cron {
+    minutes: Vec<bool>,
+    hours: Vec<bool>,
+    days: Vec<bool>,
+    months: Vec<bool>,
+    weekdays: Vec<bool>,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+enum Repeat {
+    Once,
+    Every(std::time::Duration),
+    Cron(Cron),
+}
+
+struct Job {
+    id: u64,
+    thunk: Node,
+    due: Instant,
+    repeat: Repeat,
+}
+
+thread_local! {
+    static JOBS: RefCell<Vec<Job>> = const { RefCell::new(Vec::new()) };
+    static NEXT_ID: Cell<u64> = const { Cell::new(1) };
+}
+
+fn parse_cron_field(field: &str, min: u32, max: u32) -> Result<Vec<bool>, String> {
+    let mut allowed = vec![false; usize::try_from(max).unwrap_or_default() + 1];
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (
+                range,
+                step.parse::<u32>()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| format!("Invalid cron step: {part}"))?,
+            ),
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => {
+                let bound = |value: &str| {
+                    value
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|value| (min..=max).contains(value))
+                        .ok_or_else(|| format!("Invalid cron value: {part} (expected {min}-{max})"))
+                };
+                match range.split_once('-') {
+                    Some((start, end)) => (bound(start)?, bound(end)?),
+                    None if part.contains('/') => (bound(range)?, max),
+                    None => (bound(range)?, bound(range)?),
+                }
+            }
+        };
+        for value in (start..=end).step_by(usize::try_from(step).unwrap_or(1)) {
+            allowed[usize::try_from(value).unwrap_or_default()] = true;
+        }
+    }
+    Ok(allowed)
+}
+
+impl Cron {
+    /// Parse a standard five-field expression: minute, hour, day of month,
+    /// month and day of week (0 or 7 is Sunday).
+    fn parse(expression: &str) -> Result<Self, String> {
+        let fields = expression.split_whitespace().collect::<Vec<_>>();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            return Err(format!(
+                "Invalid cron expression: '{expression}' (expected 5 fields)"
+            ));
+        };
+        let mut weekday_values = parse_cron_field(weekdays, 0, 7)?;
+        if weekday_values.pop() == Some(true) {
+            weekday_values[0] = true;
+        }
+        Ok(Self {
+            minutes: parse_cron_field(minutes, 0, 59)?,
+            hours: parse_cron_field(hours, 0, 23)?,
+            days: parse_cron_field(days, 1, 31)?,
+            months: parse_cron_field(months, 1, 12)?,
+            weekdays: weekday_values,
+            any_day: days == "*",
+            any_weekday: weekdays == "*",
+        })
+    }
+
+    fn matches_day(&self, time: &NaiveDateTime) -> bool {
+        let day = self.days[time.day() as usize];
+        let weekday = self.weekdays[time.weekday().num_days_from_sunday() as usize];
+        // As in cron, a restricted day of month and day of week match either
+        if self.any_day || self.any_weekday {
+            day && weekday
+        } else {
+            day || weekday
+        }
+    }
+
+    /// The first matching minute strictly after `after`.
+    fn next_after(&self, after: &NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut time = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        for _ in 0..CRON_SEARCH_LIMIT {
+            if !self.months[time.month() as usize] {
+                let (year, month) = if time.month() == 12 {
+                    (time.year() + 1, 1)
+                } else {
+                    (time.year(), time.month() + 1)
+                };
+                time = chrono::NaiveDate::from_ymd_opt(year, month, 1)?.and_hms_opt(0, 0, 0)?;
+            } else if !self.matches_day(&time) {
+                time = (time.date() + Duration::days(1)).and_hms_opt(0, 0, 0)?;
+            } else if !self.hours[time.hour() as usize] {
+                time = time.with_minute(0)? + Duration::hours(1);
+            } else if !self.minutes[time.minute() as usize] {
+                time += Duration::minutes(1);
+            } else {
+                return Some(time);
+            }
+        }
+        None
+    }
+
+    fn next_due(&self) -> Result<Instant, String> {
+        let mut after = chrono::Local::now().naive_local();
+        loop {
+            let next = self
+                .next_after(&after)
+                .ok_or_else(|| "Cron expression never matches".to_string())?;
+            // Local times skipped by a daylight saving change never occur
+            if let Some(local) = chrono::Local.from_local_datetime(&next).earliest() {
+                return instant_at(SystemTime::from(local));
+            }
+            after = next;
+        }
+    }
+}
+
+fn instant_at(target: SystemTime) -> Result<Instant, String> {
+    Instant::now()
+        .checked_add(target.duration_since(SystemTime::now()).unwrap_or_default())
+        .ok_or_else(|| "Scheduled time is too far in the future".to_string())
+}
+
+fn after_interval(interval: std::time::Duration) -> Result<Instant, String> {
+    Instant::now()
+        .checked_add(interval)
+        .ok_or_else(|| format!("Interval is too long: {}s", interval.as_secs()))
+}
+
+fn add_job(thunk: Node, due: Instant, repeat: Repeat) -> Node {
+    let id = NEXT_ID.with(|next_id| next_id.replace(next_id.get() + 1));
+    JOBS.with(|jobs| {
+        jobs.borrow_mut().push(Job {
+            id,
+            thunk,
+            due,
+            repeat,
+        });
+    });
+    Node::Job(id)
+}
+
+/// Remove a job, returning whether it was still scheduled.
+fn remove_job(id: u64) -> bool {
+    JOBS.with(|jobs| {
+        let mut jobs = jobs.borrow_mut();
+        let before = jobs.len();
+        jobs.retain(|job| job.id != id);
+        jobs.len() != before
+    })
+}
+
+fn eval_arguments(rest: &[Node], env: &mut Environment) -> Result<Vec<Node>, String> {
+    rest.iter().map(|n| eval(n, env)).collect()
+}
+
+fn to_interval(node: &Node) -> Result<std::time::Duration, String> {
+    let interval = match node {
+        Node::Number(ms) => u64::try_from(*ms)
+            .ok()
+            .map(std::time::Duration::from_millis),
+        Node::Duration(seconds) => u64::try_from(*seconds)
+            .ok()
+            .map(std::time::Duration::from_secs),
+        _ => None,
+    };
+    interval
+        .filter(|interval| !interval.is_zero())
+        .ok_or_else(|| format!("Invalid interval: {node} (expected a positive duration)"))
+}
+
+// Runs the thunk every interval (milliseconds or a duration), starting one
+// interval from now
+//- (test "every" (type? (let ((job (every (seconds 1) (lambda () 1)))) (begin (cancel job) job))) "job")
+pub fn eval_every(rest: &[Node], env: &mut Environment) -> Result<Node, String> {
+    match &eval_arguments(rest, env)?[..] {
+        [interval, thunk] => {
+            let interval = to_interval(interval)?;
+            Ok(add_job(
+                thunk.clone(),
+                after_interval(interval)?,
+                Repeat::Every(interval),
+            ))
+        }
+        arguments => invalid_arguments!(
+            "every",
+            arguments,
+            [
+                "[Number(milliseconds), Any(thunk)]",
+                "[Duration(interval), Any(thunk)]"
+            ]
+        ),
+    }
+}
+
+// Times in the past run as soon as the event loop starts
+//- (test "at" (let ((job (at (time 2000 1 1) (lambda () 1)))) (cancel job)) true)
+pub fn eval_at(rest: &[Node], env: &mut Environment) -> Result<Node, String> {
+    match &eval_arguments(rest, env)?[..] {
+        [Node::Time(seconds, _), thunk] => {
+            let target = match u64::try_from(*seconds) {
+                Ok(seconds) => SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(seconds),
+                Err(_) => SystemTime::UNIX_EPOCH,
+            };
+            Ok(add_job(thunk.clone(), instant_at(target)?, Repeat::Once))
+        }
+        arguments => invalid_arguments!("at", arguments, ["[Time(seconds, offset), Any(thunk)]"]),
+    }
+}
+
+// Cron expressions are evaluated in the local time zone
+//- (test "schedule" (let ((job (schedule "*/5 * * * *" (lambda () 1)))) (cancel job)) true)
+pub fn eval_schedule(rest: &[Node], env: &mut Environment) -> Result<Node, String> {
+    match &eval_arguments(rest, env)?[..] {
+        [Node::Text(expression), thunk] => {
+            let cron = Cron::parse(expression)?;
+            let due = cron.next_due()?;
+            Ok(add_job(thunk.clone(), due, Repeat::Cron(cron)))
+        }
+        arguments => invalid_arguments!(
+            "schedule",
+            arguments,
+            ["[Text(cron-expression), Any(thunk)]"]
+        ),
+    }
+}
+
+//- (test "cancel" (let ((job (every 1000 (lambda () 1)))) (begin (cancel job) (cancel job))) false)
+pub fn fn_cancel(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Job(id)] => Ok(Node::Bool(remove_job(*id))),
+        _ => invalid_arguments!("cancel", arguments, ["[Job(id)]"]),
+    }
+}
+
+// The next time after `time` matching the cron expression, in the same offset
+//- (test "cron-next" (cron-next "*/15 * * * *" (time 2025 1 1 12 7 30)) (time 2025 1 1 12 15 0))
+//- (test "cron-next" (cron-next "0 9 * * 1-5" (time 2025 1 3 10 0 0 -5)) (time 2025 1 6 9 0 0 -5))
+//- (test "cron-next" (cron-next "30 4 1,15 * 0" (time 2025 1 2)) (time 2025 1 5 4 30 0))
+//- (test "cron-next" (cron-next "0 0 29 2 *" (time 2025 1 1)) (time 2028 2 29))
+pub fn fn_cron_next(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(expression), Node::Time(seconds, offset)] => {
+            let cron = Cron::parse(expression)?;
+            let local = to_datetime(*seconds, *offset)?.naive_local();
+            let next = cron
+                .next_after(&local)
+                .ok_or_else(|| format!("Cron expression never matches: {expression}"))?;
+            from_local(&next, *offset)
+        }
+        _ => invalid_arguments!(
+            "cron-next",
+            arguments,
+            ["[Text(cron-expression), Time(seconds, offset)]"]
+        ),
+    }
+}
+
+/// Run scheduled jobs in order of their due times until none remain. Thunks
+/// are called in `env`, so they see everything defined by the script. A job
+/// that fails is cancelled and its error passed to `report`, while the other
+/// jobs keep running.
+pub fn run_event_loop(env: &mut Environment, mut report: impl FnMut(&str)) {
+    loop {
+        let Some((id, due)) = JOBS.with(|jobs| {
+            jobs.borrow()
+                .iter()
+                .min_by_key(|job| job.due)
+                .map(|job| (job.id, job.due))
+        }) else {
+            return;
+        };
+        std::thread::sleep(due.saturating_duration_since(Instant::now()));
+
+        // The job stays registered while it runs so that it can cancel itself
+        let thunk = JOBS.with(|jobs| {
+            jobs.borrow()
+                .iter()
+                .find(|job| job.id == id)
+                .map(|job| job.thunk.clone())
+                .expect("due job is registered")
+        });
+        if let Err(e) = apply(&thunk, &[], env) {
+            remove_job(id);
+            report(&format!("Job {id} failed and was cancelled: {e}"));
+            continue;
+        }
+
+        let rescheduled = JOBS.with(|jobs| {
+            let mut jobs = jobs.borrow_mut();
+            let Some(index) = jobs.iter().position(|job| job.id == id) else {
+                return Ok(());
+            };
+            let job = &mut jobs[index];
+            match &job.repeat {
+                Repeat::Once => {
+                    jobs.remove(index);
+                }
+                Repeat::Every(interval) => {
+                    job.due = job
+                        .due
+                        .checked_add(*interval)
+                        .ok_or_else(|| "Interval is too long".to_string())?
+                        .max(Instant::now());
+                }
+                Repeat::Cron(cron) => job.due = cron.next_due()?,
+            }
+            Ok::<(), String>(())
+        });
+        if let Err(e) = rescheduled {
+            remove_job(id);
+            report(&format!(
+                "Job {id} could not be rescheduled and was cancelled: {e}"
+            ));
+        }
+    }
+}
@@ -1,61 +1,429 @@
+use crate::environment::Environment;
+use crate::eval::{apply, eval};
 use crate::invalid_arguments;
+use crate::lazy::Lazy;
 use crate::node::Node;
+use std::rc::Rc;
+
+// Larger repeat counts would take gigabytes; use a lazy sequence instead
+const MAX_REPEAT: usize = 10_000_000;
 
 //- (test "zip" (zip (quote (1 2 3)) (quote (4 5 6))) (quote ((1 4) (2 5) (3 6))))
 //- (test "zip" (zip (quote ()) (quote ())) (quote ()))
 //- (test "zip" (zip (quote (1)) (quote (2))) (quote ((1 2))))
+//- (test "zip" (zip (quote (1 2)) (quote (3 4)) (quote (5 6 7))) (quote ((1 3 5) (2 4 6))))
+//- (test "zip" (zip (quote (1 2))) (quote ((1) (2))))
 pub fn fn_zip(arguments: &[Node]) -> Result<Node, String> {
-    match arguments {
-        [Node::List(list1), Node::List(list2)] => {
-            let mut zipped = Vec::new();
-            let len = list1.len().min(list2.len());
-            for i in 0..len {
-                zipped.push(Node::List(vec![list1[i].clone(), list2[i].clone()]));
-            }
+    let lists = arguments
+        .iter()
+        .map(|arg| match arg {
+            Node::List(list) => Some(list),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>();
+    match lists {
+        Some(lists) if !lists.is_empty() => {
+            let len = lists.iter().map(|list| list.len()).min().unwrap_or(0);
+            let zipped = (0..len)
+                .map(|i| Node::List(lists.iter().map(|list| list[i].clone()).collect()))
+                .collect();
             Ok(Node::List(zipped))
         }
-        _ => invalid_arguments!("zip", arguments, ["[List(list1), List(list2)]"]),
+        _ => invalid_arguments!("zip", arguments, ["[List(list1), List(list2), ...]"]),
     }
 }
 
+// Ranges are lazy, so a huge range costs nothing until it is consumed
 //- (test "range" (range 1 5) (quote (1 2 3 4)))
 //- (test "range" (range 5) (quote (0 1 2 3 4)))
 //- (test "range" (range 0 0) (quote ()))
+//- (test "range" (range 10 0 -3) (quote (10 7 4 1)))
+//- (test "range" (length (take 3 (range 100000000000))) 3)
+//- (test "range" (fold + 0 (range 1000001)) 500000500000)
 pub fn fn_range(arguments: &[Node]) -> Result<Node, String> {
+    let (start, end, step) = match arguments {
+        [Node::Number(end)] => (0, *end, 1),
+        [Node::Number(start), Node::Number(end)] => (*start, *end, 1),
+        [Node::Number(start), Node::Number(end), Node::Number(step)] => (*start, *end, *step),
+        _ => {
+            return invalid_arguments!(
+                "range",
+                arguments,
+                [
+                    "[Number(end)",
+                    "Number(start), Number(end)",
+                    "Number(start), Number(end), Number(step)]"
+                ]
+            );
+        }
+    };
+    if step == 0 {
+        return Err("Step cannot be zero".to_string());
+    }
+    Ok(Node::Lazy(Rc::new(Lazy::Range {
+        start,
+        end: Some(end),
+        step,
+    })))
+}
+
+fn to_count(name: &str, n: i64) -> Result<usize, String> {
+    usize::try_from(n).map_err(|_| format!("Invalid count for {name}: {n}"))
+}
+
+fn truthy(name: &str, result: Node) -> Result<bool, String> {
+    match result {
+        Node::Bool(b) => Ok(b),
+        other => Err(format!(
+            "Predicate for {name} must return a boolean: {other:?}"
+        )),
+    }
+}
+
+//- (test "take" (take 2 (quote (1 2 3))) (quote (1 2)))
+//- (test "take" (take 5 (quote (1 2))) (quote (1 2)))
+//- (test "take" (take 0 (quote (1 2))) (quote ()))
+//- (test "take" (realize (take 2 (lazy-range 5))) (quote (5 6)))
+pub fn fn_take(arguments: &[Node]) -> Result<Node, String> {
     match arguments {
-        [Node::Number(end)] => {
-            let mut range = Vec::new();
-            for i in 0..*end {
-                range.push(Node::Number(i));
+        [Node::Number(n), Node::List(list)] => {
+            let n = to_count("take", *n)?;
+            Ok(Node::List(list.iter().take(n).cloned().collect()))
+        }
+        [Node::Number(n), Node::Lazy(seq)] => Ok(Node::Lazy(Rc::new(Lazy::Take {
+            count: to_count("take", *n)?,
+            source: seq.clone(),
+        }))),
+        _ => invalid_arguments!(
+            "take",
+            arguments,
+            ["[Number(n), List(list)]", "[Number(n), Lazy(seq)]"]
+        ),
+    }
+}
+
+//- (test "drop" (drop 2 (quote (1 2 3))) (quote (3)))
+//- (test "drop" (drop 5 (quote (1 2))) (quote ()))
+//- (test "drop" (drop 0 (quote (1 2))) (quote (1 2)))
+//- (test "drop" (take 2 (drop 3 (lazy-range))) (quote (3 4)))
+pub fn fn_drop(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Number(n), Node::List(list)] => {
+            let n = to_count("drop", *n)?;
+            Ok(Node::List(list.iter().skip(n).cloned().collect()))
+        }
+        [Node::Number(n), Node::Lazy(seq)] => Ok(Node::Lazy(Rc::new(Lazy::Drop {
+            count: to_count("drop", *n)?,
+            source: seq.clone(),
+        }))),
+        _ => invalid_arguments!(
+            "drop",
+            arguments,
+            ["[Number(n), List(list)]", "[Number(n), Lazy(seq)]"]
+        ),
+    }
+}
+
+//- (test "take-while" (take-while odd? (quote (1 3 4 5))) (quote (1 3)))
+//- (test "take-while" (take-while odd? (quote (2 3))) (quote ()))
+//- (test "take-while" (take-while odd? (quote ())) (quote ()))
+//- (test "take-while" (take-while (lambda (x) (< x 3)) (lazy-range)) (quote (0 1 2)))
+pub fn eval_take_while(rest: &[Node], env: &mut Environment) -> Result<Node, String> {
+    let rest = rest
+        .iter()
+        .map(|n| eval(n, env))
+        .collect::<Result<Vec<_>, _>>()?;
+    match &rest[..] {
+        [function, Node::List(list)] => {
+            let mut taken = Vec::new();
+            for item in list {
+                if !truthy(
+                    "take-while",
+                    apply(function, std::slice::from_ref(item), env)?,
+                )? {
+                    break;
+                }
+                taken.push(item.clone());
+            }
+            Ok(Node::List(taken))
+        }
+        [function, Node::Lazy(seq)] => Ok(Node::Lazy(Rc::new(Lazy::TakeWhile {
+            function: function.clone(),
+            source: seq.clone(),
+            env: env.clone(),
+        }))),
+        _ => invalid_arguments!(
+            "take-while",
+            rest,
+            ["[Any(function), List(list)]", "[Any(function), Lazy(seq)]"]
+        ),
+    }
+}
+
+//- (test "drop-while" (drop-while odd? (quote (1 3 4 5))) (quote (4 5)))
+//- (test "drop-while" (drop-while odd? (quote (2 3))) (quote (2 3)))
+//- (test "drop-while" (drop-while odd? (quote (1 3))) (quote ()))
+//- (test "drop-while" (take 2 (drop-while (lambda (x) (< x 3)) (lazy-range))) (quote (3 4)))
+pub fn eval_drop_while(rest: &[Node], env: &mut Environment) -> Result<Node, String> {
+    let rest = rest
+        .iter()
+        .map(|n| eval(n, env))
+        .collect::<Result<Vec<_>, _>>()?;
+    match &rest[..] {
+        [function, Node::List(list)] => {
+            let mut start = list.len();
+            for (i, item) in list.iter().enumerate() {
+                if !truthy(
+                    "drop-while",
+                    apply(function, std::slice::from_ref(item), env)?,
+                )? {
+                    start = i;
+                    break;
+                }
             }
-            Ok(Node::List(range))
+            Ok(Node::List(list[start..].to_vec()))
         }
-        [Node::Number(start), Node::Number(end)] => {
-            let mut range = Vec::new();
-            for i in *start..*end {
-                range.push(Node::Number(i));
+        [function, Node::Lazy(seq)] => Ok(Node::Lazy(Rc::new(Lazy::DropWhile {
+            function: function.clone(),
+            source: seq.clone(),
+            env: env.clone(),
+        }))),
+        _ => invalid_arguments!(
+            "drop-while",
+            rest,
+            ["[Any(function), List(list)]", "[Any(function), Lazy(seq)]"]
+        ),
+    }
+}
+
+//- (test "partition" (partition even? (quote (1 2 3 4))) (quote ((2 4) (1 3))))
+//- (test "partition" (partition even? (quote ())) (quote (() ())))
+pub fn eval_partition(rest: &[Node], env: &mut Environment) -> Result<Node, String> {
+    let rest = rest
+        .iter()
+        .map(|n| eval(n, env))
+        .collect::<Result<Vec<_>, _>>()?;
+    match &rest[..] {
+        [function, Node::List(list)] => {
+            let mut matching = Vec::new();
+            let mut rejected = Vec::new();
+            for item in list {
+                if truthy(
+                    "partition",
+                    apply(function, std::slice::from_ref(item), env)?,
+                )? {
+                    matching.push(item.clone());
+                } else {
+                    rejected.push(item.clone());
+                }
             }
-            Ok(Node::List(range))
+            Ok(Node::List(vec![Node::List(matching), Node::List(rejected)]))
         }
-        [Node::Number(start), Node::Number(end), Node::Number(step)] => {
-            let mut range = Vec::new();
-            if *step == 0 {
-                return Err("Step cannot be zero".to_string());
+        _ => invalid_arguments!("partition", rest, ["[Any(function), List(list)]"]),
+    }
+}
+
+//- (test "chunk" (chunk 2 (quote (1 2 3 4 5))) (quote ((1 2) (3 4) (5))))
+//- (test "chunk" (chunk 3 (quote ())) (quote ()))
+pub fn fn_chunk(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Number(n), Node::List(list)] => {
+            let n = to_count("chunk", *n)?;
+            if n == 0 {
+                return Err("Chunk size cannot be zero".to_string());
             }
-            let mut current = *start;
-            while (step > &0 && current < *end) || (step < &0 && current > *end) {
-                range.push(Node::Number(current));
-                current += step;
+            Ok(Node::List(
+                list.chunks(n).map(|c| Node::List(c.to_vec())).collect(),
+            ))
+        }
+        _ => invalid_arguments!("chunk", arguments, ["[Number(size), List(list)]"]),
+    }
+}
+
+//- (test "window" (window 2 (quote (1 2 3))) (quote ((1 2) (2 3))))
+//- (test "window" (window 4 (quote (1 2 3))) (quote ()))
+pub fn fn_window(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Number(n), Node::List(list)] => {
+            let n = to_count("window", *n)?;
+            if n == 0 {
+                return Err("Window size cannot be zero".to_string());
             }
-            Ok(Node::List(range))
+            Ok(Node::List(
+                list.windows(n).map(|w| Node::List(w.to_vec())).collect(),
+            ))
+        }
+        _ => invalid_arguments!("window", arguments, ["[Number(size), List(list)]"]),
+    }
+}
+
+fn flatten(list: &[Node], depth: usize) -> Vec<Node> {
+    let mut result = Vec::new();
+    for item in list {
+        match item {
+            Node::List(inner) if depth > 0 => result.extend(flatten(inner, depth - 1)),
+            _ => result.push(item.clone()),
+        }
+    }
+    result
+}
+
+//- (test "flatten" (flatten (quote (1 (2 (3))))) (quote (1 2 (3))))
+//- (test "flatten" (flatten 2 (quote (1 (2 (3))))) (quote (1 2 3)))
+//- (test "flatten" (flatten 0 (quote (1 (2)))) (quote (1 (2))))
+pub fn fn_flatten(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::List(list)] => Ok(Node::List(flatten(list, 1))),
+        [Node::Number(depth), Node::List(list)] => {
+            let depth = to_count("flatten", *depth)?;
+            Ok(Node::List(flatten(list, depth)))
         }
         _ => invalid_arguments!(
-            "range",
+            "flatten",
             arguments,
+            ["[List(list)]", "[Number(depth), List(list)]"]
+        ),
+    }
+}
+
+//- (test "interleave" (interleave (quote (1 2 3)) (quote (a b c))) (quote (1 a 2 b 3 c)))
+//- (test "interleave" (interleave (quote (1 2 3)) (quote (a))) (quote (1 a)))
+pub fn fn_interleave(arguments: &[Node]) -> Result<Node, String> {
+    match fn_zip(arguments) {
+        Ok(Node::List(zipped)) => Ok(Node::List(flatten(&zipped, 1))),
+        _ => invalid_arguments!("interleave", arguments, ["[List(list1), List(list2), ...]"]),
+    }
+}
+
+//- (test "enumerate" (enumerate (quote (a b))) (quote ((0 a) (1 b))))
+//- (test "enumerate" (enumerate (quote ())) (quote ()))
+pub fn fn_enumerate(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::List(list)] => {
+            let mut enumerated = Vec::new();
+            for (i, item) in list.iter().enumerate() {
+                let i = i64::try_from(i).map_err(|_| "Failed to convert index".to_string())?;
+                enumerated.push(Node::List(vec![Node::Number(i), item.clone()]));
+            }
+            Ok(Node::List(enumerated))
+        }
+        _ => invalid_arguments!("enumerate", arguments, ["[List(list)]"]),
+    }
+}
+
+//- (test "group-by" (group-by even? (quote (1 2 3 4))) (quote ((false (1 3)) (true (2 4)))))
+//- (test "group-by" (group-by length (quote ("a" "bb" "c"))) (quote ((1 ("a" "c")) (2 ("bb")))))
+pub fn eval_group_by(rest: &[Node], env: &mut Environment) -> Result<Node, String> {
+    let rest = rest
+        .iter()
+        .map(|n| eval(n, env))
+        .collect::<Result<Vec<_>, _>>()?;
+    match &rest[..] {
+        [function, Node::List(list)] => {
+            let mut groups: Vec<(Node, Vec<Node>)> = Vec::new();
+            for item in list {
+                let key = apply(function, std::slice::from_ref(item), env)?;
+                match groups.iter_mut().find(|(k, _)| *k == key) {
+                    Some((_, members)) => members.push(item.clone()),
+                    None => groups.push((key, vec![item.clone()])),
+                }
+            }
+            Ok(Node::List(
+                groups
+                    .into_iter()
+                    .map(|(key, members)| Node::List(vec![key, Node::List(members)]))
+                    .collect(),
+            ))
+        }
+        _ => invalid_arguments!("group-by", rest, ["[Any(function), List(list)]"]),
+    }
+}
+
+//- (test "frequencies" (frequencies (quote (a b a))) (quote ((a 2) (b 1))))
+//- (test "frequencies" (frequencies (quote ())) (quote ()))
+pub fn fn_frequencies(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::List(list)] => {
+            let mut counts: Vec<(Node, i64)> = Vec::new();
+            for item in list {
+                match counts.iter_mut().find(|(k, _)| k == item) {
+                    Some((_, count)) => *count += 1,
+                    None => counts.push((item.clone(), 1)),
+                }
+            }
+            Ok(Node::List(
+                counts
+                    .into_iter()
+                    .map(|(item, count)| Node::List(vec![item, Node::Number(count)]))
+                    .collect(),
+            ))
+        }
+        _ => invalid_arguments!("frequencies", arguments, ["[List(list)]"]),
+    }
+}
+
+//- (test "append" (append 3 (quote (1 2))) (quote (1 2 3)))
+//- (test "append" (append 1 (quote ())) (quote (1)))
+//- (test "append" (append (quote (3)) (quote (1 2))) (quote (1 2 (3))))
+pub fn fn_append(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [item, Node::List(list)] => {
+            let mut appended = list.clone();
+            appended.push(item.clone());
+            Ok(Node::List(appended))
+        }
+        _ => invalid_arguments!("append", arguments, ["[Any(item), List(list)]"]),
+    }
+}
+
+//- (test "repeat" (repeat 3 "a") (quote ("a" "a" "a")))
+//- (test "repeat" (repeat 0 1) (quote ()))
+pub fn fn_repeat(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Number(n), item] => {
+            let n = to_count("repeat", *n)?;
+            if n > MAX_REPEAT {
+                return Err(format!("Cannot repeat {n} times: at most {MAX_REPEAT}"));
+            }
+            Ok(Node::List(vec![item.clone(); n]))
+        }
+        _ => invalid_arguments!("repeat", arguments, ["[Number(n), Any(item)]"]),
+    }
+}
+
+//- (test "iterate" (iterate inc 0 4) (quote (0 1 2 3)))
+//- (test "iterate" (iterate (lambda (x) (* x 2)) 1 5) (quote (1 2 4 8 16)))
+//- (test "iterate" (iterate inc 0 0) (quote ()))
+//- (test "iterate" (realize (take 3 (iterate inc 0))) (quote (0 1 2)))
+pub fn eval_iterate(rest: &[Node], env: &mut Environment) -> Result<Node, String> {
+    let rest = rest
+        .iter()
+        .map(|n| eval(n, env))
+        .collect::<Result<Vec<_>, _>>()?;
+    match &rest[..] {
+        [function, seed, Node::Number(n)] => {
+            let n = to_count("iterate", *n)?;
+            let mut result = Vec::new();
+            let mut current = seed.clone();
+            for i in 0..n {
+                result.push(current.clone());
+                if i + 1 < n {
+                    current = apply(function, &[current], env)?;
+                }
+            }
+            Ok(Node::List(result))
+        }
+        [function, seed] => Ok(Node::Lazy(Rc::new(Lazy::Iterate {
+            function: function.clone(),
+            seed: seed.clone(),
+            env: env.clone(),
+        }))),
+        _ => invalid_arguments!(
+            "iterate",
+            rest,
             [
-                "[Number(end)",
-                "Number(start), Number(end)",
-                "Number(start), Number(end), Number(step)]"
+                "[Any(function), Any(seed), Number(count)]",
+                "[Any(function), Any(seed)]"
             ]
         ),
     }
@@ -0,0 +1,266 @@
+use crate::compare::compare;
+use crate::invalid_arguments;
+use crate::node::Node;
+
+#[allow(clippy::cast_precision_loss)]
+fn to_floats(name: &str, list: &[Node]) -> Result<Vec<f64>, String> {
+    if list.is_empty() {
+        return Err(format!("Cannot compute {name} of an empty list"));
+    }
+    list.iter()
+        .map(|node| match node {
+            Node::Number(n) => Ok(*n as f64),
+            Node::Float(f) => Ok(*f),
+            _ => Err(format!(
+                "Cannot compute {name}: expected a list of numbers, found {node:?}"
+            )),
+        })
+        .collect()
+}
+
+fn add(name: &str, a: &Node, b: &Node) -> Result<Node, String> {
+    #[allow(clippy::cast_precision_loss)]
+    match (a, b) {
+        (Node::Number(a), Node::Number(b)) => a
+            .checked_add(*b)
+            .map(Node::Number)
+            .ok_or_else(|| format!("Overflow computing {name}")),
+        (Node::Float(a), Node::Float(b)) => Ok(Node::Float(a + b)),
+        (Node::Number(a), Node::Float(b)) => Ok(Node::Float(*a as f64 + b)),
+        (Node::Float(a), Node::Number(b)) => Ok(Node::Float(a + *b as f64)),
+        _ => Err(format!(
+            "Cannot compute {name}: expected a list of numbers, found {b:?}"
+        )),
+    }
+}
+
+#[allow(clippy::cast_precision_loss)]
+pub fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+#[allow(clippy::cast_precision_loss)]
+pub fn variance(values: &[f64]) -> f64 {
+    let mean = mean(values);
+    values.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / values.len() as f64
+}
+
+#[allow(clippy::cast_precision_loss)]
+pub fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = p / 100.0 * (sorted.len() - 1) as f64;
+    let lower = rank.floor();
+    let fraction = rank - lower;
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let lower = lower as usize;
+    if lower + 1 < sorted.len() {
+        sorted[lower] + (sorted[lower + 1] - sorted[lower]) * fraction
+    } else {
+        sorted[lower]
+    }
+}
+
+pub fn sorted(values: Vec<f64>) -> Vec<f64> {
+    let mut values = values;
+    values.sort_by(f64::total_cmp);
+    values
+}
+
+//- (test "sum" (sum (quote (1 2 3))) 6)
+//- (test "sum" (sum (quote (1 2.5))) 3.5)
+//- (test "sum" (sum (quote ())) 0)
+pub fn fn_sum(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::List(list)] => list
+            .iter()
+            .try_fold(Node::Number(0), |total, item| add("sum", &total, item)),
+        _ => invalid_arguments!("sum", arguments, ["[List(numbers)]"]),
+    }
+}
+
+//- (test "mean" (mean (quote (1 2 3))) 2.0)
+//- (test "mean" (mean (quote (1 2.0))) 1.5)
+pub fn fn_mean(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::List(list)] => Ok(Node::Float(mean(&to_floats("mean", list)?))),
+        _ => invalid_arguments!("mean", arguments, ["[List(numbers)]"]),
+    }
+}
+
+//- (test "median" (median (quote (3 1 2))) 2.0)
+//- (test "median" (median (quote (4 1 3 2))) 2.5)
+//- (test "median" (median (quote (7))) 7.0)
+pub fn fn_median(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::List(list)] => Ok(Node::Float(percentile(
+            &sorted(to_floats("median", list)?),
+            50.0,
+        ))),
+        _ => invalid_arguments!("median", arguments, ["[List(numbers)]"]),
+    }
+}
+
+//- (test "mode" (mode (quote (1 2 2 3))) 2)
+//- (test "mode" (mode (quote (1 2 3))) 1)
+//- (test "mode" (mode (quote (1.5 2 1.5))) 1.5)
+//- (test "mode" (mode (quote (2 1 1.0 2.0 1))) 1)
+pub fn fn_mode(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::List(list)] => {
+            to_floats("mode", list)?;
+            // Group equal values the way sort does, so 1 and 1.0 count as one
+            // value; ties go to the value that appears first
+            let mut order = (0..list.len()).collect::<Vec<_>>();
+            order.sort_by(|a, b| compare(&list[*a], &list[*b]));
+            let mut best: Option<(usize, usize)> = None;
+            for group in order.chunk_by(|a, b| compare(&list[*a], &list[*b]).is_eq()) {
+                let first = group.iter().copied().min().unwrap_or_default();
+                if best.is_none_or(|(best_first, best_count)| {
+                    group.len() > best_count || (group.len() == best_count && first < best_first)
+                }) {
+                    best = Some((first, group.len()));
+                }
+            }
+            best.map(|(first, _)| list[first].clone())
+                .ok_or_else(|| "Cannot compute mode of an empty list".to_string())
+        }
+        _ => invalid_arguments!("mode", arguments, ["[List(numbers)]"]),
+    }
+}
+
+//- (test "variance" (variance (quote (2 4 4 4 5 5 7 9))) 4.0)
+//- (test "variance" (variance (quote (1.0))) 0.0)
+pub fn fn_variance(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::List(list)] => Ok(Node::Float(variance(&to_floats("variance", list)?))),
+        _ => invalid_arguments!("variance", arguments, ["[List(numbers)]"]),
+    }
+}
+
+//- (test "stddev" (stddev (quote (2 4 4 4 5 5 7 9))) 2.0)
+//- (test "stddev" (stddev (quote (3 3 3))) 0.0)
+pub fn fn_stddev(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::List(list)] => Ok(Node::Float(variance(&to_floats("stddev", list)?).sqrt())),
+        _ => invalid_arguments!("stddev", arguments, ["[List(numbers)]"]),
+    }
+}
+
+//- (test "percentile" (percentile 50 (quote (1 2 3 4 5))) 3.0)
+//- (test "percentile" (percentile 25 (quote (1 2 3 4 5))) 2.0)
+//- (test "percentile" (percentile 100 (quote (5 1 3))) 5.0)
+//- (test "percentile" (percentile 90.0 (quote (0 10))) 9.0)
+pub fn fn_percentile(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [p @ (Node::Number(_) | Node::Float(_)), Node::List(list)] => {
+            let p = to_floats("percentile", std::slice::from_ref(p))?[0];
+            if !(0.0..=100.0).contains(&p) {
+                return Err(format!("Percentile must be between 0 and 100, got {p}"));
+            }
+            Ok(Node::Float(percentile(
+                &sorted(to_floats("percentile", list)?),
+                p,
+            )))
+        }
+        _ => invalid_arguments!(
+            "percentile",
+            arguments,
+            ["[Number(p), List(numbers)]", "[Float(p), List(numbers)]"]
+        ),
+    }
+}
+
+//- (test "histogram" (histogram 2 (quote (0 1 2 3 4))) (quote ((0.0 2.0 2) (2.0 4.0 3))))
+//- (test "histogram" (histogram 1 (quote (5 5))) (quote ((5.0 5.0 2))))
+pub fn fn_histogram(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Number(buckets), Node::List(list)] => {
+            let values = to_floats("histogram", list)?;
+            if let Some(value) = values.iter().find(|value| !value.is_finite()) {
+                return Err(format!("Cannot make a histogram of {value}"));
+            }
+            let buckets = usize::try_from(*buckets)
+                .ok()
+                .filter(|b| *b > 0)
+                .ok_or_else(|| format!("Bucket count must be positive, got {buckets}"))?;
+            let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+            let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            #[allow(clippy::cast_precision_loss)]
+            let width = (max - min) / buckets as f64;
+
+            let mut counts = vec![0_i64; buckets];
+            for value in values {
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                let index = if width > 0.0 {
+                    (((value - min) / width) as usize).min(buckets - 1)
+                } else {
+                    0
+                };
+                counts[index] += 1;
+            }
+
+            Ok(Node::List(
+                counts
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, count)| {
+                        #[allow(clippy::cast_precision_loss)]
+                        let lower = min + width * i as f64;
+                        Node::List(vec![
+                            Node::Float(lower),
+                            Node::Float(lower + width),
+                            Node::Number(count),
+                        ])
+                    })
+                    .collect(),
+            ))
+        }
+        _ => invalid_arguments!("histogram", arguments, ["[Number(buckets), List(numbers)]"]),
+    }
+}
+
+fn extreme(name: &str, list: &[Node], keep: fn(f64, f64) -> bool) -> Result<Node, String> {
+    let values = to_floats(name, list)?;
+    let mut best = 0;
+    for (i, value) in values.iter().enumerate() {
+        if keep(*value, values[best]) {
+            best = i;
+        }
+    }
+    Ok(list[best].clone())
+}
+
+//- (test "min" (min (quote (3 1 2))) 1)
+//- (test "min" (min (quote (3 1.5 2))) 1.5)
+pub fn fn_min(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::List(list)] => extreme("min", list, |a, b| a < b),
+        _ => invalid_arguments!("min", arguments, ["[List(numbers)]"]),
+    }
+}
+
+//- (test "max" (max (quote (3 1 2))) 3)
+//- (test "max" (max (quote (3 4.5 2))) 4.5)
+pub fn fn_max(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::List(list)] => extreme("max", list, |a, b| a > b),
+        _ => invalid_arguments!("max", arguments, ["[List(numbers)]"]),
+    }
+}
+
+//- (test "cumulative-sum" (cumulative-sum (quote (1 2 3))) (quote (1 3 6)))
+//- (test "cumulative-sum" (cumulative-sum (quote (1 0.5))) (quote (1 1.5)))
+//- (test "cumulative-sum" (cumulative-sum (quote ())) (quote ()))
+pub fn fn_cumulative_sum(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::List(list)] => {
+            let mut result = Vec::with_capacity(list.len());
+            let mut total = Node::Number(0);
+            for item in list {
+                total = add("cumulative-sum", &total, item)?;
+                result.push(total.clone());
+            }
+            Ok(Node::List(result))
+        }
+        _ => invalid_arguments!("cumulative-sum", arguments, ["[List(numbers)]"]),
+    }
+}
@@ -1,5 +1,57 @@
 use crate::invalid_arguments;
 use crate::node::Node;
+use std::borrow::Cow;
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+// Positions in text count grapheme clusters (user-perceived characters), so
+// an emoji or a letter with combining accents is a single position
+
+/// Byte offset of the grapheme at `index`, or of the end of `text` when
+/// `index` equals the grapheme count.
+fn grapheme_offset(text: &str, index: usize) -> Option<usize> {
+    text.grapheme_indices(true)
+        .map(|(offset, _)| offset)
+        .chain(std::iter::once(text.len()))
+        .nth(index)
+}
+
+/// A grapheme as a value: a character when it is a single code point, and
+/// text otherwise, e.g. for an emoji with a skin tone.
+///
+/// The type therefore depends on the encoding as well as on what is shown:
+/// `"é"` in NFC is one code point and gives a character, while in NFD it
+/// is `e` plus a combining accent and gives text. `=` and the text builtins
+/// accept both alike, so code reading graphemes need not check which it got.
+pub fn grapheme_node(grapheme: &str) -> Node {
+    let mut chars = grapheme.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Node::Char(c),
+        _ => Node::Text(grapheme.to_string()),
+    }
+}
+
+/// Characters are accepted wherever text is, as one-character text.
+fn chars_as_text(arguments: &[Node]) -> Cow<'_, [Node]> {
+    if !arguments.iter().any(|node| matches!(node, Node::Char(_))) {
+        return Cow::Borrowed(arguments);
+    }
+    Cow::Owned(
+        arguments
+            .iter()
+            .map(|node| match node {
+                Node::Char(c) => Node::Text(c.to_string()),
+                other => other.clone(),
+            })
+            .collect(),
+    )
+}
+
+fn count(n: usize) -> Result<Node, String> {
+    i64::try_from(n)
+        .map(Node::Number)
+        .map_err(|_| format!("Count {n} is too large for a Number"))
+}
 
 macro_rules! all_list {
     ($args:expr) => {
@@ -18,7 +70,9 @@ macro_rules! all_text {
 //- (test "concat" (concat (quote ()) (quote (1))) (quote (1)))
 //- (test "concat" (concat (quote (1 2)) (quote (4 1)) (quote (1))) (quote (1 2 4 1 1)))
 //- (test "concat" (concat "Foo" "Bar" "Baz") "FooBarBaz")
+//- (test "concat" (concat "a" #\b (string-ref "cd" 0)) "abc")
 pub fn fn_concat(arguments: &[Node]) -> Result<Node, String> {
+    let arguments: &[Node] = &chars_as_text(arguments);
     match arguments {
         [Node::List(_), ..] => {
             if !all_list!(arguments) {
@@ -67,7 +121,9 @@ pub fn fn_concat(arguments: &[Node]) -> Result<Node, String> {
 //- (test "split" (split "," "foo,bar,baz") (quote ("foo" "bar" "baz")))
 //- (test "split" (split "," "foo") (quote ("foo")))
 //- (test "split" (split "," "") (quote ("")))
+//- (test "split" (split #\, "a,b") (quote ("a" "b")))
 pub fn fn_split(arguments: &[Node]) -> Result<Node, String> {
+    let arguments: &[Node] = &chars_as_text(arguments);
     match arguments {
         [Node::Text(delimiter), Node::Text(text)] => {
             let split = text
@@ -86,6 +142,7 @@ pub fn fn_split(arguments: &[Node]) -> Result<Node, String> {
 //- (test "strip" (strip "foo") "foo")
 //- (test "strip" (strip "") "")
 pub fn fn_strip(arguments: &[Node]) -> Result<Node, String> {
+    let arguments: &[Node] = &chars_as_text(arguments);
     match arguments {
         [Node::Text(s)] => Ok(Node::Text(s.trim().to_string())),
         _ => {
@@ -97,16 +154,21 @@ pub fn fn_strip(arguments: &[Node]) -> Result<Node, String> {
 //- (test "join" (join "," (quote ("foo" "bar" "baz"))) "foo,bar,baz")
 //- (test "join" (join "," (quote ("foo"))) "foo")
 //- (test "join" (join "," (quote ())) "")
+//- (test "join" (join "" (string->list "añ👍🏽")) "añ👍🏽")
+//- (test "join" (string-length (join "" (string->list "abc"))) 3)
 pub fn fn_join(arguments: &[Node]) -> Result<Node, String> {
     match arguments {
         [Node::Text(delimiter), Node::List(list)] => {
             let joined = list
                 .iter()
-                .filter_map(|node| match node {
-                    Node::Text(s) => Some(s.clone()),
-                    _ => None,
+                .map(|node| match node {
+                    Node::Text(s) => Ok(s.clone()),
+                    Node::Char(c) => Ok(c.to_string()),
+                    other => Err(format!(
+                        "Cannot join {other:?}: expected text or a character"
+                    )),
                 })
-                .collect::<Vec<_>>()
+                .collect::<Result<Vec<_>, _>>()?
                 .join(delimiter);
             Ok(Node::Text(joined))
         }
@@ -116,19 +178,39 @@ pub fn fn_join(arguments: &[Node]) -> Result<Node, String> {
     }
 }
 
+// Like get and binary-search, an item missing from a list gives (); a
+// substring missing from text is an error
 //- (test "index-of" (index-of "foo" "foobar") 0)
 //- (test "index-of" (index-of "bar" "foobar") 3)
+//- (test "index-of" (index-of 2 (quote (1 2 3))) 1)
+//- (test "index-of" (index-of "b" (quote ("a" "b"))) 1)
+//- (test "index-of" (index-of "bar" "fööbar") 3)
+//- (test "index-of" (index-of 5 (quote (1 2 3))) ())
+//- (test "index-of" (index-of #\b "abc") 1)
+//- (test "index-of" (index-of #\b (string->list "abc")) 1)
 pub fn fn_index_of(arguments: &[Node]) -> Result<Node, String> {
     match arguments {
-        [Node::Text(substring), Node::Text(text)] => match text.find(substring) {
+        [item, Node::List(list)] => match list.iter().position(|other| other == item) {
             Some(index) => match index.try_into() {
                 Ok(index) => Ok(Node::Number(index)),
                 Err(_) => Err(format!("Index {index} is too large for a Number",)),
             },
+            None => Ok(Node::List(vec![])),
+        },
+        [Node::Char(c), Node::Text(text)] => match text.find(*c) {
+            Some(offset) => count(text[..offset].graphemes(true).count()),
+            None => Err(format!("Character '{c}' not found in '{text}'",)),
+        },
+        [Node::Text(substring), Node::Text(text)] => match text.find(substring) {
+            Some(offset) => count(text[..offset].graphemes(true).count()),
             None => Err(format!("Substring '{substring}' not found in '{text}'",)),
         },
         _ => {
-            invalid_arguments!("index-of", arguments, ["[Text(substring), Text(text)]"])
+            invalid_arguments!(
+                "index-of",
+                arguments,
+                ["[Text(substring), Text(text)]", "[Any(item), List(list)]"]
+            )
         }
     }
 }
@@ -136,22 +218,25 @@ pub fn fn_index_of(arguments: &[Node]) -> Result<Node, String> {
 //- (test "substring" (substring "foobar" 0 3) "foo")
 //- (test "substring" (substring "foobar" 3 6) "bar")
 //- (test "substring" (substring "foobar" 3 3) "")
+//- (test "substring" (substring "née👍🏽!" 1 4) "ée👍🏽")
 pub fn fn_substring(arguments: &[Node]) -> Result<Node, String> {
+    let arguments: &[Node] = &chars_as_text(arguments);
     match arguments {
         [Node::Text(text), Node::Number(start), Node::Number(end)] => {
             let start = usize::try_from(*start)
                 .map_err(|_| format!("Start index {start} is too large for a Number"))?;
             let end = usize::try_from(*end)
                 .map_err(|_| format!("End index {end} is too large for a Number"))?;
-            if start <= end && end <= text.len() {
-                Ok(Node::Text(text[start..end].to_string()))
-            } else {
-                Err(format!(
+            match (grapheme_offset(text, start), grapheme_offset(text, end)) {
+                (Some(start), Some(end)) if start <= end => {
+                    Ok(Node::Text(text[start..end].to_string()))
+                }
+                _ => Err(format!(
                     "Invalid substring range: {}..{} for text of length {}",
                     start,
                     end,
-                    text.len()
-                ))
+                    text.graphemes(true).count()
+                )),
             }
         }
         _ => {
@@ -168,6 +253,7 @@ pub fn fn_substring(arguments: &[Node]) -> Result<Node, String> {
 //- (test "replace" (replace "bar" "foo" "foobar") "foofoo")
 //- (test "replace" (replace "baz" "foo" "foobar") "foobar")
 pub fn fn_replace(arguments: &[Node]) -> Result<Node, String> {
+    let arguments: &[Node] = &chars_as_text(arguments);
     match arguments {
         [Node::Text(old), Node::Text(new), Node::Text(text)] => {
             let replaced = text.replace(old, new);
@@ -182,7 +268,9 @@ pub fn fn_replace(arguments: &[Node]) -> Result<Node, String> {
 //- (test "upper" (upper "foo") "FOO")
 //- (test "upper" (upper "FOO") "FOO")
 //- (test "upper" (upper "") "")
+//- (test "upper" (list->string (map upper (string->list "ab"))) "AB")
 pub fn fn_upper(arguments: &[Node]) -> Result<Node, String> {
+    let arguments: &[Node] = &chars_as_text(arguments);
     match arguments {
         [Node::Text(s)] => Ok(Node::Text(s.to_uppercase())),
         _ => {
@@ -195,6 +283,7 @@ pub fn fn_upper(arguments: &[Node]) -> Result<Node, String> {
 //- (test "lower" (lower "FOO") "foo")
 //- (test "lower" (lower "") "")
 pub fn fn_lower(arguments: &[Node]) -> Result<Node, String> {
+    let arguments: &[Node] = &chars_as_text(arguments);
     match arguments {
         [Node::Text(s)] => Ok(Node::Text(s.to_lowercase())),
         _ => {
@@ -207,6 +296,7 @@ pub fn fn_lower(arguments: &[Node]) -> Result<Node, String> {
 //- (test "starts-with?" (starts-with? "bar" "foobar") false)
 //- (test "starts-with?" (starts-with? "" "foobar") true)
 pub fn fn_starts_with(arguments: &[Node]) -> Result<Node, String> {
+    let arguments: &[Node] = &chars_as_text(arguments);
     match arguments {
         [Node::Text(prefix), Node::Text(text)] => Ok(Node::Bool(text.starts_with(prefix))),
         _ => {
@@ -219,6 +309,7 @@ pub fn fn_starts_with(arguments: &[Node]) -> Result<Node, String> {
 //- (test "ends-with?" (ends-with? "foo" "foobar") false)
 //- (test "ends-with?" (ends-with? "" "foobar") true)
 pub fn fn_ends_with(arguments: &[Node]) -> Result<Node, String> {
+    let arguments: &[Node] = &chars_as_text(arguments);
     match arguments {
         [Node::Text(suffix), Node::Text(text)] => Ok(Node::Bool(text.ends_with(suffix))),
         _ => {
@@ -226,3 +317,101 @@ pub fn fn_ends_with(arguments: &[Node]) -> Result<Node, String> {
         }
     }
 }
+
+//- (test "string-length" (string-length "née") 3)
+//- (test "string-length" (string-length "👍🏽🇩🇪") 2)
+//- (test "string-length" (string-length "") 0)
+pub fn fn_string_length(arguments: &[Node]) -> Result<Node, String> {
+    let arguments: &[Node] = &chars_as_text(arguments);
+    match arguments {
+        [Node::Text(text)] => count(text.graphemes(true).count()),
+        _ => invalid_arguments!("string-length", arguments, ["[Text(text)]"]),
+    }
+}
+
+//- (test "char-count" (char-count "👍🏽") 2)
+//- (test "char-count" (char-count (nfd "é")) 2)
+pub fn fn_char_count(arguments: &[Node]) -> Result<Node, String> {
+    let arguments: &[Node] = &chars_as_text(arguments);
+    match arguments {
+        [Node::Text(text)] => count(text.chars().count()),
+        _ => invalid_arguments!("char-count", arguments, ["[Text(text)]"]),
+    }
+}
+
+//- (test "byte-length" (byte-length "é") 2)
+//- (test "byte-length" (byte-length "abc") 3)
+pub fn fn_byte_length(arguments: &[Node]) -> Result<Node, String> {
+    let arguments: &[Node] = &chars_as_text(arguments);
+    match arguments {
+        [Node::Text(text)] => count(text.len()),
+        _ => invalid_arguments!("byte-length", arguments, ["[Text(text)]"]),
+    }
+}
+
+//- (test "string-ref" (string-ref "a👍🏽b" 1) "👍🏽")
+//- (test "string-ref" (string-ref "abc" 2) #\c)
+//- (test "string-ref" (type? (string-ref "abc" 0)) (type? (car (string->list "abc"))))
+//- (test "string-ref" (type? (string-ref (nfc "é") 0)) "char")
+//- (test "string-ref" (type? (string-ref (nfd "é") 0)) "text")
+//- (test "string-ref" (= (string-ref (nfd "é") 0) (nfd "é")) true)
+pub fn fn_string_ref(arguments: &[Node]) -> Result<Node, String> {
+    let arguments: &[Node] = &chars_as_text(arguments);
+    match arguments {
+        [Node::Text(text), Node::Number(index)] => usize::try_from(*index)
+            .ok()
+            .and_then(|i| text.graphemes(true).nth(i))
+            .map(grapheme_node)
+            .ok_or_else(|| {
+                format!(
+                    "Index {index} out of range for text of length {}",
+                    text.graphemes(true).count()
+                )
+            }),
+        _ => invalid_arguments!("string-ref", arguments, ["[Text(text), Number(index)]"]),
+    }
+}
+
+//- (test "nfc" (byte-length (nfc (nfd "é"))) 2)
+//- (test "nfc" (= (nfc (nfd "é")) "é") true)
+pub fn fn_nfc(arguments: &[Node]) -> Result<Node, String> {
+    let arguments: &[Node] = &chars_as_text(arguments);
+    match arguments {
+        [Node::Text(text)] => Ok(Node::Text(text.nfc().collect())),
+        _ => invalid_arguments!("nfc", arguments, ["[Text(text)]"]),
+    }
+}
+
+//- (test "nfd" (byte-length (nfd "é")) 3)
+//- (test "nfd" (string-length (nfd "é")) 1)
+pub fn fn_nfd(arguments: &[Node]) -> Result<Node, String> {
+    let arguments: &[Node] = &chars_as_text(arguments);
+    match arguments {
+        [Node::Text(text)] => Ok(Node::Text(text.nfd().collect())),
+        _ => invalid_arguments!("nfd", arguments, ["[Text(text)]"]),
+    }
+}
+
+// Full Unicode case folding, for case-insensitive comparison
+//- (test "fold-case" (fold-case "Straße") "strasse")
+//- (test "fold-case" (= (fold-case "ΣΑΣ") (fold-case "σας")) true)
+pub fn fn_fold_case(arguments: &[Node]) -> Result<Node, String> {
+    let arguments: &[Node] = &chars_as_text(arguments);
+    match arguments {
+        [Node::Text(text)] => Ok(Node::Text(caseless::default_case_fold_str(text))),
+        _ => invalid_arguments!("fold-case", arguments, ["[Text(text)]"]),
+    }
+}
+
+// Columns the text occupies in a terminal: wide CJK and emoji count as two,
+// combining marks as zero
+//- (test "display-width" (display-width "abc") 3)
+//- (test "display-width" (display-width "日本") 4)
+//- (test "display-width" (display-width (nfd "é")) 1)
+pub fn fn_display_width(arguments: &[Node]) -> Result<Node, String> {
+    let arguments: &[Node] = &chars_as_text(arguments);
+    match arguments {
+        [Node::Text(text)] => count(unicode_width::UnicodeWidthStr::width(text.as_str())),
+        _ => invalid_arguments!("display-width", arguments, ["[Text(text)]"]),
+    }
+}
@@ -1,4 +1,8 @@
+use crate::invalid_arguments;
+use crate::list::{make_record, record_get};
 use crate::node::Node;
+use crate::terminal;
+use std::io::Write;
 
 pub fn fn_system(arguments: &[Node]) -> Result<Node, String> {
     if arguments.len() == 1 {
@@ -8,17 +12,145 @@ pub fn fn_system(arguments: &[Node]) -> Result<Node, String> {
         let output = std::process::Command::new("sh")
             .arg("-c")
             .arg(command)
-            .output();
-        if let Ok(output) = output {
-            return Ok(Node::Text(
-                String::from_utf8_lossy(&output.stdout).to_string(),
-            ));
-        }
+            .output()
+            .map_err(|e| format!("Failed to run command '{command}': {e}"))?;
+        return Ok(Node::Text(
+            String::from_utf8_lossy(&output.stdout).to_string(),
+        ));
     }
 
     Err("Invalid arguments for system".to_string())
 }
 
+fn text_option<'a>(options: &'a [Node], key: &str) -> Result<Option<&'a str>, String> {
+    match record_get(options, &Node::Symbol(key.to_string())) {
+        None => Ok(None),
+        Some(Node::Text(value)) => Ok(Some(value)),
+        Some(other) => Err(format!("Option '{key}' must be text, found {other:?}")),
+    }
+}
+
+fn run(name: &str, arguments: &[Node]) -> Result<(std::process::Output, i64), String> {
+    let (program, args, options) = match arguments {
+        [Node::Text(program)] => (program, &[][..], &[][..]),
+        [Node::Text(program), Node::List(args)] => (program, &args[..], &[][..]),
+        [Node::Text(program), Node::List(args), Node::List(options)] => {
+            (program, &args[..], &options[..])
+        }
+        _ => {
+            return invalid_arguments!(
+                name,
+                arguments,
+                [
+                    "[Text(program)]",
+                    "[Text(program), List(args)]",
+                    "[Text(program), List(args), List(options)]"
+                ]
+            );
+        }
+    };
+
+    let mut command = std::process::Command::new(program);
+    for arg in args {
+        match arg {
+            Node::Text(arg) => command.arg(arg),
+            other => command.arg(other.to_string()),
+        };
+    }
+    if let Some(cwd) = text_option(options, "cwd")? {
+        command.current_dir(cwd);
+    }
+    if let Some(Node::Bool(true)) = record_get(options, &Node::Symbol("clear-env".to_string())) {
+        command.env_clear();
+    }
+    match record_get(options, &Node::Symbol("env".to_string())) {
+        None => {}
+        Some(Node::List(vars)) => {
+            for var in vars {
+                match var {
+                    Node::List(pair) if pair.len() == 2 => {
+                        command.env(pair[0].to_string(), pair[1].to_string());
+                    }
+                    _ => return Err(format!("Invalid env entry, expected (name value): {var}")),
+                }
+            }
+        }
+        Some(other) => return Err(format!("Option 'env' must be a list, found {other:?}")),
+    }
+    let stdin = text_option(options, "stdin")?;
+
+    command
+        .stdin(if stdin.is_some() {
+            std::process::Stdio::piped()
+        } else {
+            std::process::Stdio::null()
+        })
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped());
+
+    let start = std::time::Instant::now();
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to run '{program}': {e}"))?;
+    if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        let input = input.to_string();
+        std::thread::spawn(move || pipe.write_all(input.as_bytes()));
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Failed to wait for '{program}': {e}"))?;
+    let duration = i64::try_from(start.elapsed().as_millis())
+        .map_err(|_| "Time duration too large".to_string())?;
+
+    Ok((output, duration))
+}
+
+fn output_record(output: &std::process::Output, duration: i64) -> Node {
+    make_record(vec![
+        (
+            "stdout",
+            Node::Text(String::from_utf8_lossy(&output.stdout).to_string()),
+        ),
+        (
+            "stderr",
+            Node::Text(String::from_utf8_lossy(&output.stderr).to_string()),
+        ),
+        (
+            "exit-code",
+            output
+                .status
+                .code()
+                .map_or_else(|| Node::List(vec![]), |code| Node::Number(i64::from(code))),
+        ),
+        ("duration-ms", Node::Number(duration)),
+    ])
+}
+
+//- (test "run" (get (quote stdout) (run "echo" (quote ("foo" "bar")))) "foo bar\n")
+//- (test "run" (get (quote exit-code) (run "sh" (quote ("-c" "exit 3")))) 3)
+//- (test "run" (get (quote stderr) (run "sh" (quote ("-c" "echo oops >&2")))) "oops\n")
+//- (test "run" (get (quote stdout) (run "cat" (list) (quote ((stdin "piped"))))) "piped")
+//- (test "run" (get (quote stdout) (run "pwd" (list) (quote ((cwd "/"))))) "/\n")
+//- (test "run" (get (quote stdout) (run "sh" (quote ("-c" "echo $FOO")) (quote ((env ((FOO bar))))))) "bar\n")
+pub fn fn_run(arguments: &[Node]) -> Result<Node, String> {
+    let (output, duration) = run("run", arguments)?;
+    Ok(output_record(&output, duration))
+}
+
+//- (test "run!" (get (quote exit-code) (run! "true")) 0)
+pub fn fn_run_checked(arguments: &[Node]) -> Result<Node, String> {
+    let (output, duration) = run("run!", arguments)?;
+    if !output.status.success() {
+        return Err(format!(
+            "Command '{}' failed with {}\n{}",
+            arguments[0],
+            output.status,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    Ok(output_record(&output, duration))
+}
+
 pub fn fn_version(_: &[Node]) -> Result<Node, String> {
     Ok(Node::Text(format!(
         "Lich version {}",
@@ -26,6 +158,157 @@ pub fn fn_version(_: &[Node]) -> Result<Node, String> {
     )))
 }
 
-pub fn fn_exit(_: &[Node]) -> Result<Node, String> {
-    std::process::exit(0);
+//- (test "getenv" (getenv "LICH_UNSET_VARIABLE") ())
+//- (test "getenv" (begin (setenv "LICH_TEST" "1") (getenv "LICH_TEST")) "1")
+pub fn fn_getenv(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(name)] => {
+            Ok(std::env::var(name).map_or_else(|_| Node::List(vec![]), Node::Text))
+        }
+        _ => invalid_arguments!("getenv", arguments, ["[Text(name)]"]),
+    }
+}
+
+pub fn fn_setenv(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(name), value] => {
+            if name.is_empty() || name.contains(['=', '\0']) {
+                return Err(format!("Invalid environment variable name: {name:?}"));
+            }
+            let value = value.to_string();
+            if value.contains('\0') {
+                return Err(format!("Invalid environment variable value: {value:?}"));
+            }
+            // SAFETY: the interpreter does not read the environment from other threads.
+            unsafe { std::env::set_var(name, value) };
+            Ok(Node::Bool(true))
+        }
+        _ => invalid_arguments!("setenv", arguments, ["[Text(name), Any(value)]"]),
+    }
+}
+
+//- (test "unsetenv" (begin (setenv "LICH_TEST" "1") (unsetenv "LICH_TEST") (getenv "LICH_TEST")) ())
+pub fn fn_unsetenv(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(name)] => {
+            if name.is_empty() || name.contains(['=', '\0']) {
+                return Err(format!("Invalid environment variable name: {name:?}"));
+            }
+            // SAFETY: the interpreter does not read the environment from other threads.
+            unsafe { std::env::remove_var(name) };
+            Ok(Node::Bool(true))
+        }
+        _ => invalid_arguments!("unsetenv", arguments, ["[Text(name)]"]),
+    }
+}
+
+//- (test "environ" (begin (setenv "LICH_TEST" "2") (get "LICH_TEST" (environ))) "2")
+pub fn fn_environ(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [] => {
+            let mut vars = std::env::vars_os()
+                .map(|(name, value)| {
+                    (
+                        name.to_string_lossy().to_string(),
+                        value.to_string_lossy().to_string(),
+                    )
+                })
+                .collect::<Vec<_>>();
+            vars.sort();
+            Ok(make_record(
+                vars.iter()
+                    .map(|(name, value)| (name.as_str(), Node::Text(value.clone())))
+                    .collect(),
+            ))
+        }
+        _ => invalid_arguments!("environ", arguments, ["[]"]),
+    }
+}
+
+pub fn fn_cwd(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [] => {
+            let cwd = std::env::current_dir()
+                .map_err(|e| format!("Failed to get current directory: {e}"))?;
+            Ok(Node::Text(cwd.to_string_lossy().to_string()))
+        }
+        _ => invalid_arguments!("cwd", arguments, ["[]"]),
+    }
+}
+
+pub fn fn_chdir(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(path)] => {
+            std::env::set_current_dir(path)
+                .map_err(|e| format!("Failed to change directory to {path}: {e}"))?;
+            Ok(Node::Bool(true))
+        }
+        _ => invalid_arguments!("chdir", arguments, ["[Text(path)]"]),
+    }
+}
+
+pub fn fn_pid(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [] => Ok(Node::Number(i64::from(std::process::id()))),
+        _ => invalid_arguments!("pid", arguments, ["[]"]),
+    }
+}
+
+pub fn fn_hostname(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [] => {
+            let mut buffer = [0u8; 256];
+            // SAFETY: the buffer is valid for writes of its full length.
+            let result = unsafe { libc::gethostname(buffer.as_mut_ptr().cast(), buffer.len()) };
+            if result != 0 {
+                return Err(format!(
+                    "Failed to get hostname: {}",
+                    std::io::Error::last_os_error()
+                ));
+            }
+            let end = buffer.iter().position(|&b| b == 0).unwrap_or(buffer.len());
+            Ok(Node::Text(
+                String::from_utf8_lossy(&buffer[..end]).to_string(),
+            ))
+        }
+        _ => invalid_arguments!("hostname", arguments, ["[]"]),
+    }
+}
+
+pub fn fn_username(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [] => {
+            // SAFETY: getpwuid returns either null or a pointer to a static passwd entry,
+            // which is only read before any other call could overwrite it.
+            let name = unsafe {
+                let passwd = libc::getpwuid(libc::getuid());
+                if passwd.is_null() || (*passwd).pw_name.is_null() {
+                    None
+                } else {
+                    Some(
+                        std::ffi::CStr::from_ptr((*passwd).pw_name)
+                            .to_string_lossy()
+                            .to_string(),
+                    )
+                }
+            };
+            name.or_else(|| std::env::var("USER").ok())
+                .map(Node::Text)
+                .ok_or_else(|| "Failed to determine username".to_string())
+        }
+        _ => invalid_arguments!("username", arguments, ["[]"]),
+    }
+}
+
+pub fn fn_exit(arguments: &[Node]) -> Result<Node, String> {
+    let code = match arguments {
+        [] => 0,
+        [Node::Number(code)] => {
+            i32::try_from(*code).map_err(|_| format!("Invalid exit code: {code}"))?
+        }
+        _ => return invalid_arguments!("exit", arguments, ["[]", "[Number(code)]"]),
+    };
+    // process::exit skips destructors, so undo terminal mode changes here
+    terminal::restore();
+    std::process::exit(code)
 }
@@ -0,0 +1,509 @@
+use crate::environment::Environment;
+use crate::eval::eval;
+use crate::invalid_arguments;
+use crate::list::record_get;
+use crate::node::Node;
+use crate::parse::parse;
+
+// Lazy sequences are realized for {{#each}}, so infinite ones need a bound
+const EACH_LIMIT: usize = 100_000;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Block {
+    Each,
+    If,
+    Unless,
+}
+
+impl Block {
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Each => "each",
+            Self::If => "if",
+            Self::Unless => "unless",
+        }
+    }
+}
+
+/// What a tag refers to: a dotted path into the data, or a Lich expression.
+enum Expression {
+    Path(Vec<String>),
+    Lich(Node),
+}
+
+impl std::fmt::Display for Expression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Path(path) if path.is_empty() => write!(f, "this"),
+            Self::Path(path) => write!(f, "{}", path.join(".")),
+            Self::Lich(node) => write!(f, "{node}"),
+        }
+    }
+}
+
+enum Tag {
+    Value {
+        expression: Expression,
+        escape: bool,
+    },
+    Open {
+        block: Block,
+        expression: Expression,
+    },
+    Else,
+    Close(Block),
+    Comment,
+}
+
+enum Token {
+    Text(String),
+    Tag { tag: Tag, line: usize },
+}
+
+enum Part {
+    Text(String),
+    Value {
+        expression: Expression,
+        escape: bool,
+        line: usize,
+    },
+    Block {
+        block: Block,
+        expression: Expression,
+        body: Vec<Part>,
+        otherwise: Vec<Part>,
+        line: usize,
+    },
+}
+
+/// One level of `{{#each}}` nesting; the data map itself is the outermost.
+struct Scope {
+    value: Node,
+    index: Option<usize>,
+}
+
+fn at_line(line: usize, message: &str) -> String {
+    format!("Template error on line {line}: {message}")
+}
+
+fn parse_expression(source: &str) -> Result<Expression, String> {
+    if source.starts_with('(') {
+        return parse_lich(source);
+    }
+    if source.is_empty() {
+        return Err("Empty tag".to_string());
+    }
+    if source == "this" || source == "." {
+        return Ok(Expression::Path(vec![]));
+    }
+    let path = source
+        .split('.')
+        .map(|segment| {
+            if segment.is_empty() || segment.contains(char::is_whitespace) {
+                Err(format!("Invalid variable name: {source}"))
+            } else {
+                Ok(segment.to_string())
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Expression::Path(path))
+}
+
+fn parse_lich(source: &str) -> Result<Expression, String> {
+    match &parse(source)?[..] {
+        [node] => Ok(Expression::Lich(node.clone())),
+        _ => Err(format!("Expected a single Lich expression: {source}")),
+    }
+}
+
+fn parse_tag(content: &str, raw: bool) -> Result<Tag, String> {
+    let value = |expression| Tag::Value {
+        expression,
+        escape: !raw,
+    };
+    if let Some(source) = content.strip_prefix('=') {
+        return parse_lich(source.trim()).map(value);
+    }
+    let is_block = content.starts_with(['#', '/', '!']) || content == "else";
+    if raw && is_block {
+        return Err(format!("Unexpected triple braces around {{{{{content}}}}}"));
+    }
+    let block = |name: &str| match name {
+        "each" => Ok(Block::Each),
+        "if" => Ok(Block::If),
+        "unless" => Ok(Block::Unless),
+        _ => Err(format!("Unknown block: {name}")),
+    };
+    if content.starts_with('!') {
+        Ok(Tag::Comment)
+    } else if content == "else" {
+        Ok(Tag::Else)
+    } else if let Some(name) = content.strip_prefix('/') {
+        block(name.trim()).map(Tag::Close)
+    } else if let Some(open) = content.strip_prefix('#') {
+        let (name, source) = open.split_once(char::is_whitespace).unwrap_or((open, ""));
+        Ok(Tag::Open {
+            block: block(name)?,
+            expression: parse_expression(source.trim())?,
+        })
+    } else {
+        parse_expression(content).map(value)
+    }
+}
+
+/// Split a template into text and tags. Block tags, `{{else}}` and comments
+/// that sit alone on a line take the whole line with them, so that they do
+/// not leave blank lines in the output.
+fn tokenize(template: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut text = String::new();
+    let mut rest = template;
+    let mut line = 1;
+    let mut line_has_tag = false;
+    while let Some(start) = rest.find("{{") {
+        let before = &rest[..start];
+        if before.contains('\n') {
+            line_has_tag = false;
+        }
+        line += before.matches('\n').count();
+        text.push_str(before);
+
+        let raw = rest[start..].starts_with("{{{");
+        let (open, close) = if raw { ("{{{", "}}}") } else { ("{{", "}}") };
+        let inner = start + open.len();
+        let end = rest[inner..]
+            .find(close)
+            .map(|offset| inner + offset)
+            .ok_or_else(|| at_line(line, &format!("Unclosed tag: {open}")))?;
+        let content = &rest[inner..end];
+        let tag = parse_tag(content.trim(), raw).map_err(|e| at_line(line, &e))?;
+        let tag_line = line;
+        line += content.matches('\n').count();
+        rest = &rest[end + close.len()..];
+
+        if !matches!(tag, Tag::Value { .. }) {
+            let line_start = text.rfind('\n').map_or(0, |index| index + 1);
+            let line_end = rest.find('\n');
+            let blank = |s: &str| s.chars().all(|c| matches!(c, ' ' | '\t' | '\r'));
+            if !line_has_tag
+                && blank(&text[line_start..])
+                && blank(&rest[..line_end.unwrap_or(rest.len())])
+            {
+                text.truncate(line_start);
+                rest = match line_end {
+                    Some(index) => {
+                        line += 1;
+                        &rest[index + 1..]
+                    }
+                    None => "",
+                };
+            } else {
+                line_has_tag = true;
+            }
+        } else {
+            line_has_tag = true;
+        }
+
+        if !text.is_empty() {
+            tokens.push(Token::Text(std::mem::take(&mut text)));
+        }
+        if !matches!(tag, Tag::Comment) {
+            tokens.push(Token::Tag {
+                tag,
+                line: tag_line,
+            });
+        }
+    }
+    text.push_str(rest);
+    if !text.is_empty() {
+        tokens.push(Token::Text(text));
+    }
+    Ok(tokens)
+}
+
+/// Build parts until the end of the template or the end of the open block,
+/// returning the block's body and its `{{else}}` branch.
+fn parse_parts(
+    tokens: &mut std::vec::IntoIter<Token>,
+    open: Option<(Block, usize)>,
+) -> Result<(Vec<Part>, Vec<Part>), String> {
+    let mut body = Vec::new();
+    let mut otherwise = Vec::new();
+    let mut in_else = false;
+    loop {
+        let parts = if in_else { &mut otherwise } else { &mut body };
+        match tokens.next() {
+            None => {
+                return match open {
+                    Some((block, line)) => Err(at_line(
+                        line,
+                        &format!("Unclosed {{{{#{}}}}}", block.name()),
+                    )),
+                    None => Ok((body, otherwise)),
+                };
+            }
+            Some(Token::Text(text)) => parts.push(Part::Text(text)),
+            Some(Token::Tag { tag, line }) => match tag {
+                Tag::Value { expression, escape } => parts.push(Part::Value {
+                    expression,
+                    escape,
+                    line,
+                }),
+                Tag::Open { block, expression } => {
+                    let (block_body, block_otherwise) = parse_parts(tokens, Some((block, line)))?;
+                    parts.push(Part::Block {
+                        block,
+                        expression,
+                        body: block_body,
+                        otherwise: block_otherwise,
+                        line,
+                    });
+                }
+                Tag::Else if open.is_some() && !in_else => in_else = true,
+                Tag::Else => return Err(at_line(line, "Unexpected {{else}}")),
+                Tag::Close(block) if open.is_some_and(|(open, _)| open == block) => {
+                    return Ok((body, otherwise));
+                }
+                Tag::Close(block) => {
+                    return Err(at_line(
+                        line,
+                        &format!("Unexpected {{{{/{}}}}}", block.name()),
+                    ));
+                }
+                Tag::Comment => {}
+            },
+        }
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// The fields of a record such as `((name "Ann") (age 7))`.
+fn record_fields(node: &Node) -> Vec<(&str, &Node)> {
+    let Node::List(fields) = node else {
+        return vec![];
+    };
+    fields
+        .iter()
+        .filter_map(|field| match field {
+            Node::List(pair) => match &pair[..] {
+                [Node::Symbol(key) | Node::Text(key), value] => Some((key.as_str(), value)),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+fn is_truthy(value: Option<&Node>) -> bool {
+    match value {
+        None | Some(Node::Bool(false)) => false,
+        Some(Node::List(items)) => !items.is_empty(),
+        Some(Node::Text(text)) => !text.is_empty(),
+        Some(_) => true,
+    }
+}
+
+// Scope fields are bound in one child environment of the caller's, so Lich
+// expressions see them without copying the environment for every tag.
+struct Renderer {
+    env: Environment,
+    scopes: Vec<Scope>,
+}
+
+impl Renderer {
+    fn push_scope(&mut self, scope: Scope) {
+        for (key, value) in record_fields(&scope.value) {
+            self.env.insert(key, value.clone());
+        }
+        self.env.insert("this", scope.value.clone());
+        self.scopes.push(scope);
+    }
+
+    /// Drop the innermost scope, restoring the fields it shadowed.
+    fn pop_scope(&mut self) {
+        let Some(popped) = self.scopes.pop() else {
+            return;
+        };
+        for (key, _) in record_fields(&popped.value) {
+            let outer = self.scopes.iter().rev().find_map(|scope| {
+                record_fields(&scope.value)
+                    .into_iter()
+                    .find(|(candidate, _)| *candidate == key)
+                    .map(|(_, value)| value.clone())
+            });
+            match outer {
+                Some(value) => self.env.insert(key, value),
+                None => self.env.remove(key),
+            }
+        }
+        match self.scopes.last() {
+            Some(scope) => self.env.insert("this", scope.value.clone()),
+            None => self.env.remove("this"),
+        }
+    }
+
+    /// Look a path up in the innermost scope that has its first segment.
+    fn lookup(&self, path: &[String]) -> Option<Node> {
+        let innermost = self.scopes.last()?;
+        let Some((first, rest)) = path.split_first() else {
+            return Some(innermost.value.clone());
+        };
+        if first == "@index" {
+            return innermost
+                .index
+                .and_then(|index| i64::try_from(index).ok())
+                .map(Node::Number);
+        }
+        let mut value = self
+            .scopes
+            .iter()
+            .rev()
+            .find_map(|scope| match &scope.value {
+                Node::List(record) => record_get(record, &Node::Symbol(first.clone())),
+                _ => None,
+            })?;
+        for segment in rest {
+            let Node::List(record) = value else {
+                return None;
+            };
+            value = record_get(record, &Node::Symbol(segment.clone()))?;
+        }
+        Some(value.clone())
+    }
+
+    /// Evaluate an expression; missing paths are `None` so that blocks can
+    /// test for optional values.
+    fn evaluate(&mut self, expression: &Expression) -> Result<Option<Node>, String> {
+        match expression {
+            Expression::Path(path) => Ok(self.lookup(path)),
+            Expression::Lich(node) => eval(node, &mut self.env).map(Some),
+        }
+    }
+
+    fn render(&mut self, parts: &[Part], output: &mut String) -> Result<(), String> {
+        for part in parts {
+            match part {
+                Part::Text(text) => output.push_str(text),
+                Part::Value {
+                    expression,
+                    escape,
+                    line,
+                } => {
+                    let value = self
+                        .evaluate(expression)
+                        .map_err(|e| at_line(*line, &e))?
+                        .ok_or_else(|| {
+                            at_line(*line, &format!("Undefined variable: {expression}"))
+                        })?;
+                    let text = value.to_string();
+                    output.push_str(&if *escape { escape_html(&text) } else { text });
+                }
+                Part::Block {
+                    block,
+                    expression,
+                    body,
+                    otherwise,
+                    line,
+                } => {
+                    let value = self.evaluate(expression).map_err(|e| at_line(*line, &e))?;
+                    match block {
+                        Block::Each => {
+                            let items = match value {
+                                Some(Node::List(items)) => items,
+                                Some(Node::Lazy(sequence)) => sequence
+                                    .realize_at_most(EACH_LIMIT)
+                                    .map_err(|e| at_line(*line, &e))?,
+                                None => vec![],
+                                Some(other) => {
+                                    return Err(at_line(
+                                        *line,
+                                        &format!("{{{{#each}}}} expects a list, found {other}"),
+                                    ));
+                                }
+                            };
+                            if items.is_empty() {
+                                self.render(otherwise, output)?;
+                            }
+                            for (index, item) in items.into_iter().enumerate() {
+                                self.push_scope(Scope {
+                                    value: item,
+                                    index: Some(index),
+                                });
+                                let result = self.render(body, output);
+                                self.pop_scope();
+                                result?;
+                            }
+                        }
+                        Block::If | Block::Unless => {
+                            let truthy = is_truthy(value.as_ref()) == (*block == Block::If);
+                            self.render(if truthy { body } else { otherwise }, output)?;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Values are HTML-escaped unless written with triple braces. Missing values
+// are errors, except as the subject of a block, where they count as false or
+// empty.
+//- (test "render-template" (render-template "Hello, {{name}}!" (quote ((name "Ann")))) "Hello, Ann!")
+//- (test "render-template" (render-template "{{user.address.city}}" (quote ((user ((address ((city "Oslo")))))))) "Oslo")
+//- (test "render-template" (render-template "{{#each items}}[{{@index}}:{{this}}]{{/each}}" (quote ((items (a b c))))) "[0:a][1:b][2:c]")
+//- (test "render-template" (render-template "{{#each people}}{{name}} from {{town}};{{/each}}" (quote ((town "Bergen") (people (((name "Ann")) ((name "Bo"))))))) "Ann from Bergen;Bo from Bergen;")
+//- (test "render-template" (render-template "{{#each items}}x{{else}}none{{/each}}" (quote ((items ())))) "none")
+//- (test "render-template" (render-template "{{#if admin}}yes{{else}}no{{/if}}" (quote ((admin false)))) "no")
+//- (test "render-template" (render-template "{{#if missing}}yes{{/if}}{{#unless missing}}no{{/unless}}" (quote ())) "no")
+//- (test "render-template" (render-template "{{html}} {{{html}}}" (quote ((html "<b>&</b>")))) "&lt;b&gt;&amp;&lt;/b&gt; <b>&</b>")
+//- (test "render-template" (render-template "{{= (* n 2)}} {{#if (> n 3)}}big{{/if}}" (quote ((n 5)))) "10 big")
+//- (test "render-template" (render-template "{{#each (range 1 4)}}{{= (* this this)}} {{/each}}" (quote ())) "1 4 9 ")
+//- (test "render-template" (render-template "a{{! note }}b" (quote ())) "ab")
+//- (test "render-template" (render-template "{{#each xs}}{{= n}}{{/each}}{{= n}}" (quote ((n 0) (xs (((n 1)) ((n 2))))))) "120")
+//- (test "render-template" (render-template "{{#each xs}}{{= this}}{{/each}}{{= (length this)}}" (quote ((xs (a b))))) "ab1")
+//- (test "render-template" (render-template "{{#each (take 3 (lazy-range))}}{{this}}{{/each}}" (quote ())) "012")
+//- (test "render-template" (render-template "<ul>\n  {{#each xs}}\n  <li>{{this}}</li>\n  {{/each}}\n</ul>" (quote ((xs (1 2))))) "<ul>\n  <li>1</li>\n  <li>2</li>\n</ul>")
+pub fn eval_render_template(rest: &[Node], env: &mut Environment) -> Result<Node, String> {
+    let arguments = rest
+        .iter()
+        .map(|n| eval(n, env))
+        .collect::<Result<Vec<_>, _>>()?;
+    match &arguments[..] {
+        [Node::Text(template), data @ Node::List(_)] => {
+            let mut tokens = tokenize(template)?.into_iter();
+            let (parts, _) = parse_parts(&mut tokens, None)?;
+            let mut renderer = Renderer {
+                env: Environment::from_parent(std::mem::take(env)),
+                scopes: vec![],
+            };
+            renderer.push_scope(Scope {
+                value: data.clone(),
+                index: None,
+            });
+            let mut output = String::new();
+            let result = renderer.render(&parts, &mut output);
+            *env = renderer.env.into_parent().unwrap_or_default();
+            result.map(|()| Node::Text(output))
+        }
+        _ => invalid_arguments!(
+            "render-template",
+            arguments,
+            ["[Text(template), List(data)]"]
+        ),
+    }
+}
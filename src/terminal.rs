@@ -1,6 +1,126 @@
+use crate::invalid_arguments;
+use crate::list::make_record;
 use crate::node::Node;
+use std::cell::RefCell;
 use std::io::Write;
 
+#[derive(Clone, PartialEq)]
+struct Cell {
+    ch: char,
+    style: String,
+}
+
+impl Cell {
+    fn blank() -> Self {
+        Self {
+            ch: ' ',
+            style: String::new(),
+        }
+    }
+}
+
+// Far larger than any real terminal, but small enough to allocate
+const MAX_SCREEN_CELLS: usize = 1_000_000;
+
+/// A grid of cells drawn into off-screen and written to the terminal by
+/// `present`, which only emits the cells that changed since the last frame.
+struct Screen {
+    width: usize,
+    height: usize,
+    back: Vec<Cell>,
+    front: Vec<Option<Cell>>,
+}
+
+impl Screen {
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            back: vec![Cell::blank(); width * height],
+            front: vec![None; width * height],
+        }
+    }
+
+    /// The part of `start..end` that lies within `0..size`, so that drawing
+    /// loops never run past the edge of the screen.
+    fn clip(start: i64, end: i64, size: usize) -> std::ops::Range<i64> {
+        start.max(0)..end.min(i64::try_from(size).unwrap_or(i64::MAX))
+    }
+
+    fn put(&mut self, x: i64, y: i64, ch: char, style: &str) {
+        let (Ok(x), Ok(y)) = (usize::try_from(x), usize::try_from(y)) else {
+            return;
+        };
+        if x < self.width && y < self.height {
+            self.back[y * self.width + x] = Cell {
+                ch,
+                style: style.to_string(),
+            };
+        }
+    }
+
+    fn present(&mut self) -> String {
+        let mut output = String::new();
+        for (index, cell) in self.back.iter().enumerate() {
+            if self.front[index].as_ref() == Some(cell) {
+                continue;
+            }
+            let (x, y) = (index % self.width, index / self.width);
+            output.push_str(&format!("\x1b[{};{}H", y + 1, x + 1));
+            if cell.style.is_empty() {
+                output.push(cell.ch);
+            } else {
+                output.push_str(&format!("{}{}\x1b[0m", cell.style, cell.ch));
+            }
+            self.front[index] = Some(cell.clone());
+        }
+        output
+    }
+}
+
+#[derive(Default)]
+struct TerminalState {
+    original_mode: Option<libc::termios>,
+    cursor_hidden: bool,
+    alternate_screen: bool,
+    screen: Option<Screen>,
+}
+
+thread_local! {
+    static STATE: RefCell<TerminalState> = RefCell::new(TerminalState::default());
+}
+
+/// Undo every mode change made through this module: raw mode, a hidden
+/// cursor and the alternate screen. Safe to call more than once.
+pub fn restore() {
+    let _ = STATE.try_with(|state| {
+        let mut state = state.borrow_mut();
+        if let Some(original) = state.original_mode.take() {
+            // SAFETY: `original` was filled in by tcgetattr on the same descriptor
+            unsafe {
+                libc::tcsetattr(libc::STDIN_FILENO, libc::TCSAFLUSH, &raw const original);
+            }
+        }
+        let mut stdout = std::io::stdout();
+        if std::mem::take(&mut state.cursor_hidden) {
+            let _ = write!(stdout, "\x1b[?25h");
+        }
+        if std::mem::take(&mut state.alternate_screen) {
+            let _ = write!(stdout, "\x1b[?1049l");
+        }
+        let _ = stdout.flush();
+    });
+}
+
+/// Restores the terminal when dropped, including while unwinding from a panic.
+pub struct RestoreGuard;
+
+impl Drop for RestoreGuard {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
 fn flush() -> Result<(), String> {
     if let Err(e) = std::io::stdout().flush() {
         return Err(format!("Failed to flush stdout: {e}"));
@@ -16,77 +136,268 @@ pub fn fn_clear(_: &[Node]) -> Result<Node, String> {
 }
 
 pub fn fn_alternate_screen(_: &[Node]) -> Result<Node, String> {
-    print!(r"[?1049h");
+    print!("\x1b[?1049h");
     flush()?;
-
-    std::thread::sleep(std::time::Duration::from_secs(1));
-    println!("Displaying on Alternate Screen");
-    std::thread::sleep(std::time::Duration::from_secs(1));
+    STATE.with(|state| state.borrow_mut().alternate_screen = true);
 
     Ok(Node::Bool(true))
 }
 
 pub fn fn_normal_screen(_: &[Node]) -> Result<Node, String> {
-    print!(r"[?1049l");
+    print!("\x1b[?1049l");
     flush()?;
+    STATE.with(|state| state.borrow_mut().alternate_screen = false);
 
     Ok(Node::Bool(true))
 }
 
-pub fn fn_fg(arguments: &[Node]) -> Result<Node, String> {
-    if arguments.len() == 1 {
-        if let Node::Text(color) = &arguments[0] {
-            print!(
-                "{}",
-                match color.as_str() {
-                    "black" => "[30m",
-                    "red" => "[31m",
-                    "green" => "[32m",
-                    "yellow" => "[33m",
-                    "blue" => "[34m",
-                    "magenta" => "[35m",
-                    "cyan" => "[36m",
-                    "white" => "[37m",
-                    _ => return Err(format!("Invalid color: {color}")),
+const COLOR_NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+const STYLES: [(&str, u8); 7] = [
+    ("bold", 1),
+    ("dim", 2),
+    ("italic", 3),
+    ("underline", 4),
+    ("blink", 5),
+    ("reverse", 7),
+    ("strikethrough", 9),
+];
+
+#[derive(Clone, Copy, PartialEq)]
+enum ColorMode {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorMode {
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Always => "always",
+            Self::Never => "never",
+        }
+    }
+}
+
+thread_local! {
+    static COLOR_MODE: std::cell::Cell<ColorMode> = const { std::cell::Cell::new(ColorMode::Auto) };
+}
+
+/// Colors are emitted only to a terminal and only when `NO_COLOR` is unset,
+/// unless overridden with `set-color-mode`.
+fn colors_enabled() -> bool {
+    use std::io::IsTerminal;
+
+    match COLOR_MODE.with(std::cell::Cell::get) {
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+        ColorMode::Auto => {
+            std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+                && std::io::stdout().is_terminal()
+        }
+    }
+}
+
+enum Color {
+    Basic(u8),
+    Bright(u8),
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    /// Colors are names like "red" or "bright-red", 256-color palette
+    /// indices, or "#rrggbb" hex triples.
+    fn parse(node: &Node) -> Result<Self, String> {
+        let invalid = || format!("Invalid color: {node}");
+        match node {
+            Node::Number(index) => u8::try_from(*index)
+                .map(Self::Indexed)
+                .map_err(|_| invalid()),
+            Node::Text(text) => {
+                if let Some(hex) = text.strip_prefix('#') {
+                    let channel = |range: std::ops::Range<usize>| {
+                        hex.get(range)
+                            .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                            .ok_or_else(invalid)
+                    };
+                    if hex.len() != 6 {
+                        return Err(invalid());
+                    }
+                    return Ok(Self::Rgb(channel(0..2)?, channel(2..4)?, channel(4..6)?));
                 }
-            );
+                if let Ok(index) = text.parse::<u8>() {
+                    return Ok(Self::Indexed(index));
+                }
+                let (bright, name) = match text.strip_prefix("bright-") {
+                    Some(name) => (true, name),
+                    None => (false, text.as_str()),
+                };
+                let index = COLOR_NAMES
+                    .iter()
+                    .position(|candidate| *candidate == name)
+                    .and_then(|index| u8::try_from(index).ok())
+                    .ok_or_else(invalid)?;
+                Ok(if bright {
+                    Self::Bright(index)
+                } else {
+                    Self::Basic(index)
+                })
+            }
+            _ => Err(invalid()),
         }
-    } else if arguments.is_empty() {
-        print!("[0m");
+    }
+
+    /// The SGR parameters selecting this color.
+    fn parameters(&self, background: bool) -> String {
+        let offset = if background { 10 } else { 0 };
+        match self {
+            Self::Basic(index) => (30 + offset + index).to_string(),
+            Self::Bright(index) => (90 + offset + index).to_string(),
+            Self::Indexed(index) => format!("{};5;{index}", 38 + offset),
+            Self::Rgb(r, g, b) => format!("{};2;{r};{g};{b}", 38 + offset),
+        }
+    }
+}
+
+fn color_code(color: &Node, background: bool) -> Result<String, String> {
+    let parameters = Color::parse(color)?.parameters(background);
+    Ok(if colors_enabled() {
+        format!("\x1b[{parameters}m")
     } else {
-        return Err("Invalid arguments for fg".to_string());
+        String::new()
+    })
+}
+
+fn reset_code() -> &'static str {
+    if colors_enabled() { "\x1b[0m" } else { "" }
+}
+
+pub fn fn_fg(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [] => print!("{}", reset_code()),
+        [color] => print!("{}", color_code(color, false)?),
+        _ => {
+            return invalid_arguments!(
+                "fg",
+                arguments,
+                ["[]", "[Text(color)]", "[Number(color-index)]"]
+            );
+        }
     }
 
     Ok(Node::Bool(true))
 }
 
 pub fn fn_bg(arguments: &[Node]) -> Result<Node, String> {
-    if arguments.len() == 1 {
-        if let Node::Text(color) = &arguments[0] {
-            print!(
-                "{}",
-                match color.as_str() {
-                    "black" => "[40m",
-                    "red" => "[41m",
-                    "green" => "[42m",
-                    "yellow" => "[43m",
-                    "blue" => "[44m",
-                    "magenta" => "[45m",
-                    "cyan" => "[46m",
-                    "white" => "[47m",
-                    _ => return Err(format!("Invalid color: {color}")),
-                }
+    match arguments {
+        [] => print!("{}", reset_code()),
+        [color] => print!("{}", color_code(color, true)?),
+        _ => {
+            return invalid_arguments!(
+                "bg",
+                arguments,
+                ["[]", "[Text(color)]", "[Number(color-index)]"]
             );
         }
-    } else if arguments.is_empty() {
-        print!("[0m");
-    } else {
-        return Err("Invalid arguments for bg".to_string());
     }
 
     Ok(Node::Bool(true))
 }
 
+//- (test "rgb" (rgb 255 128 0) "#ff8000")
+pub fn fn_rgb(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Number(r), Node::Number(g), Node::Number(b)] => {
+            let channel = |value: &i64| {
+                u8::try_from(*value).map_err(|_| format!("Color channel out of range: {value}"))
+            };
+            Ok(Node::Text(format!(
+                "#{:02x}{:02x}{:02x}",
+                channel(r)?,
+                channel(g)?,
+                channel(b)?
+            )))
+        }
+        _ => invalid_arguments!("rgb", arguments, ["[Number(r), Number(g), Number(b)]"]),
+    }
+}
+
+// Returns the previous mode, so that it can be restored afterwards
+//- (test "set-color-mode" (let ((previous (set-color-mode "never"))) (let ((result (styled "hi" "bold"))) (begin (set-color-mode previous) result))) "hi")
+//- (test "set-color-mode" (let ((previous (set-color-mode "never"))) (set-color-mode previous)) "never")
+pub fn fn_set_color_mode(arguments: &[Node]) -> Result<Node, String> {
+    let mode = match arguments {
+        [Node::Text(mode)] => match mode.as_str() {
+            "auto" => ColorMode::Auto,
+            "always" => ColorMode::Always,
+            "never" => ColorMode::Never,
+            _ => {
+                return Err(format!(
+                    "Invalid color mode: {mode} (expected auto, always or never)"
+                ));
+            }
+        },
+        _ => return invalid_arguments!("set-color-mode", arguments, ["[Text(mode)]"]),
+    };
+    let previous = COLOR_MODE.with(|current| current.replace(mode));
+    Ok(Node::Text(previous.name().to_string()))
+}
+
+// Each attribute is a style name, a foreground color, or a background color
+// prefixed with "bg:", e.g. (styled "alert" "bold" "bright-white" "bg:#aa0000")
+//- (test "styled" (let ((previous (set-color-mode "always"))) (let ((result (replace (bytes->string (hex-decode "1b")) "^" (styled "hi" "bold" "red")))) (begin (set-color-mode previous) result))) "^[1;31mhi^[0m")
+//- (test "styled" (let ((previous (set-color-mode "always"))) (let ((result (replace (bytes->string (hex-decode "1b")) "^" (styled "hi" 208 "bg:#0000ff")))) (begin (set-color-mode previous) result))) "^[38;5;208;48;2;0;0;255mhi^[0m")
+//- (test "styled" (let ((previous (set-color-mode "always"))) (let ((result (replace (bytes->string (hex-decode "1b")) "^" (styled "hi" "bright-green" "bg:bright-black")))) (begin (set-color-mode previous) result))) "^[92;100mhi^[0m")
+//- (test "styled" (let ((previous (set-color-mode "never"))) (let ((result (styled "hi" "italic"))) (begin (set-color-mode previous) result))) "hi")
+pub fn fn_styled(arguments: &[Node]) -> Result<Node, String> {
+    let Some((Node::Text(text), attributes)) = arguments.split_first() else {
+        return invalid_arguments!("styled", arguments, ["[Text(text), Any(attribute)...]"]);
+    };
+    let mut parameters = vec![];
+    for attribute in attributes {
+        let style = match attribute {
+            Node::Text(name) => STYLES.iter().find(|(style, _)| style == name),
+            _ => None,
+        };
+        parameters.push(match (style, attribute) {
+            (Some((_, code)), _) => code.to_string(),
+            (None, Node::Text(name)) if name.starts_with("bg:") => {
+                Color::parse(&Node::Text(name["bg:".len()..].to_string()))?.parameters(true)
+            }
+            (None, _) => Color::parse(attribute)?.parameters(false),
+        });
+    }
+    if parameters.is_empty() || !colors_enabled() {
+        return Ok(Node::Text(text.clone()));
+    }
+    Ok(Node::Text(format!(
+        "\x1b[{}m{text}\x1b[0m",
+        parameters.join(";")
+    )))
+}
+
+fn ansi_pattern() -> &'static regex::Regex {
+    static PATTERN: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    PATTERN.get_or_init(|| {
+        regex::Regex::new(r"\x1b(\[[0-?]*[ -/]*[@-~]|\][^\x07\x1b]*(\x07|\x1b\\)|[@-Z\\-_])")
+            .expect("ANSI escape pattern is valid")
+    })
+}
+
+//- (test "strip-ansi" (strip-ansi (concat (let ((previous (set-color-mode "always"))) (let ((result (styled "hi" "bold" "#ff0000"))) (begin (set-color-mode previous) result))) " there")) "hi there")
+//- (test "strip-ansi" (strip-ansi (concat (bytes->string (hex-decode "1b")) "]0;title" (bytes->string (hex-decode "07")) "x")) "x")
+//- (test "strip-ansi" (strip-ansi "plain") "plain")
+pub fn fn_strip_ansi(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(text)] => Ok(Node::Text(ansi_pattern().replace_all(text, "").to_string())),
+        _ => invalid_arguments!("strip-ansi", arguments, ["[Text(text)]"]),
+    }
+}
+
 pub fn fn_set_cursor_pos(arguments: &[Node]) -> Result<Node, String> {
     if arguments.len() == 2 {
         if let (Node::Number(x), Node::Number(y)) = (&arguments[0], &arguments[1]) {
@@ -100,3 +411,457 @@ pub fn fn_set_cursor_pos(arguments: &[Node]) -> Result<Node, String> {
 
     Ok(Node::Bool(true))
 }
+
+pub fn fn_enter_raw_mode(arguments: &[Node]) -> Result<Node, String> {
+    if !arguments.is_empty() {
+        return invalid_arguments!("enter-raw-mode", arguments, ["[]"]);
+    }
+    STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        if state.original_mode.is_some() {
+            return Ok(Node::Bool(false));
+        }
+        // SAFETY: termios is plain data, and both calls only read or write
+        // the struct passed to them
+        unsafe {
+            let mut original: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(libc::STDIN_FILENO, &raw mut original) != 0 {
+                return Err(format!(
+                    "Failed to read terminal mode: {}",
+                    std::io::Error::last_os_error()
+                ));
+            }
+            let mut raw = original;
+            libc::cfmakeraw(&raw mut raw);
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSAFLUSH, &raw const raw) != 0 {
+                return Err(format!(
+                    "Failed to enter raw mode: {}",
+                    std::io::Error::last_os_error()
+                ));
+            }
+            state.original_mode = Some(original);
+        }
+        Ok(Node::Bool(true))
+    })
+}
+
+pub fn fn_leave_raw_mode(arguments: &[Node]) -> Result<Node, String> {
+    if !arguments.is_empty() {
+        return invalid_arguments!("leave-raw-mode", arguments, ["[]"]);
+    }
+    let Some(original) = STATE.with(|state| state.borrow_mut().original_mode.take()) else {
+        return Ok(Node::Bool(false));
+    };
+    // SAFETY: `original` was filled in by tcgetattr on the same descriptor
+    if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSAFLUSH, &raw const original) } != 0 {
+        return Err(format!(
+            "Failed to leave raw mode: {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+    Ok(Node::Bool(true))
+}
+
+pub fn fn_hide_cursor(_: &[Node]) -> Result<Node, String> {
+    print!("\x1b[?25l");
+    flush()?;
+    STATE.with(|state| state.borrow_mut().cursor_hidden = true);
+
+    Ok(Node::Bool(true))
+}
+
+pub fn fn_show_cursor(_: &[Node]) -> Result<Node, String> {
+    print!("\x1b[?25h");
+    flush()?;
+    STATE.with(|state| state.borrow_mut().cursor_hidden = false);
+
+    Ok(Node::Bool(true))
+}
+
+fn terminal_size() -> (usize, usize) {
+    // SAFETY: winsize is plain data filled in by the ioctl
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    if unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &raw mut size) } == 0
+        && size.ws_col > 0
+    {
+        return (usize::from(size.ws_col), usize::from(size.ws_row));
+    }
+    let from_env = |name: &str, default: usize| {
+        std::env::var(name)
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(default)
+    };
+    (from_env("COLUMNS", 80), from_env("LINES", 24))
+}
+
+//- (test "terminal-size" (map car (terminal-size)) (quote (columns rows)))
+pub fn fn_terminal_size(arguments: &[Node]) -> Result<Node, String> {
+    if !arguments.is_empty() {
+        return invalid_arguments!("terminal-size", arguments, ["[]"]);
+    }
+    let (columns, rows) = terminal_size();
+    Ok(make_record(vec![
+        (
+            "columns",
+            Node::Number(i64::try_from(columns).unwrap_or(i64::MAX)),
+        ),
+        (
+            "rows",
+            Node::Number(i64::try_from(rows).unwrap_or(i64::MAX)),
+        ),
+    ]))
+}
+
+/// Wait up to `timeout_ms` (forever if negative) for stdin to become readable.
+fn wait_for_input(timeout_ms: i32) -> Result<bool, String> {
+    let mut descriptor = libc::pollfd {
+        fd: libc::STDIN_FILENO,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    // SAFETY: a single valid pollfd is passed along with its count
+    match unsafe { libc::poll(&raw mut descriptor, 1, timeout_ms) } {
+        -1 => Err(format!(
+            "Failed to wait for input: {}",
+            std::io::Error::last_os_error()
+        )),
+        ready => Ok(ready > 0),
+    }
+}
+
+fn read_byte() -> Result<Option<u8>, String> {
+    let mut byte = 0u8;
+    // SAFETY: reads at most one byte into a valid one-byte buffer
+    match unsafe { libc::read(libc::STDIN_FILENO, (&raw mut byte).cast(), 1) } {
+        1 => Ok(Some(byte)),
+        0 => Ok(None),
+        _ => Err(format!(
+            "Failed to read key: {}",
+            std::io::Error::last_os_error()
+        )),
+    }
+}
+
+struct Key {
+    name: String,
+    ctrl: bool,
+    alt: bool,
+    shift: bool,
+}
+
+fn key_event(name: &str, ctrl: bool, alt: bool, shift: bool) -> Key {
+    Key {
+        name: name.to_string(),
+        ctrl,
+        alt,
+        shift,
+    }
+}
+
+impl Key {
+    fn into_node(self) -> Node {
+        make_record(vec![
+            ("key", Node::Text(self.name)),
+            ("ctrl", Node::Bool(self.ctrl)),
+            ("alt", Node::Bool(self.alt)),
+            ("shift", Node::Bool(self.shift)),
+        ])
+    }
+}
+
+/// Decode one key press from the bytes a terminal sends for it.
+fn parse_key(bytes: &[u8]) -> Key {
+    match bytes {
+        [b'\r' | b'\n'] => key_event("enter", false, false, false),
+        [b'\t'] => key_event("tab", false, false, false),
+        [0x7f | 0x08] => key_event("backspace", false, false, false),
+        [0x1b] => key_event("escape", false, false, false),
+        [0] => key_event("space", true, false, false),
+        [byte @ 1..=26] => key_event(&char::from(b'a' + byte - 1).to_string(), true, false, false),
+        [0x1b, b'[' | b'O', rest @ ..] => parse_escape_sequence(rest),
+        [0x1b, rest @ ..] => Key {
+            alt: true,
+            ..parse_key(rest)
+        },
+        _ => {
+            let text = String::from_utf8_lossy(bytes).to_string();
+            let shift = text.chars().any(char::is_uppercase);
+            key_event(&text, false, false, shift)
+        }
+    }
+}
+
+/// Decode CSI and SS3 sequences such as "[A" or "[1;5C" (ctrl+right).
+fn parse_escape_sequence(sequence: &[u8]) -> Key {
+    let text = String::from_utf8_lossy(sequence);
+    let Some(last) = text.chars().last() else {
+        return key_event("escape", false, false, false);
+    };
+    let parameters = text[..text.len() - last.len_utf8()]
+        .split(';')
+        .map(|p| p.parse::<u32>().unwrap_or(1))
+        .collect::<Vec<_>>();
+    let modifiers = parameters.get(1).copied().unwrap_or(1).saturating_sub(1);
+    let key = match (last, parameters[0]) {
+        ('A', _) => "up",
+        ('B', _) => "down",
+        ('C', _) => "right",
+        ('D', _) => "left",
+        ('H', _) | ('~', 1 | 7) => "home",
+        ('F', _) | ('~', 4 | 8) => "end",
+        ('P', _) | ('~', 11) => "f1",
+        ('Q', _) | ('~', 12) => "f2",
+        ('R', _) | ('~', 13) => "f3",
+        ('S', _) | ('~', 14) => "f4",
+        ('~', 2) => "insert",
+        ('~', 3) => "delete",
+        ('~', 5) => "page-up",
+        ('~', 6) => "page-down",
+        ('~', 15) => "f5",
+        ('~', 17) => "f6",
+        ('~', 18) => "f7",
+        ('~', 19) => "f8",
+        ('~', 20) => "f9",
+        ('~', 21) => "f10",
+        ('~', 23) => "f11",
+        ('~', 24) => "f12",
+        ('Z', _) => return key_event("tab", false, false, true),
+        _ => "unknown",
+    };
+    key_event(
+        key,
+        modifiers & 4 != 0,
+        modifiers & 2 != 0,
+        modifiers & 1 != 0,
+    )
+}
+
+// Returns a record like ((key "up") (ctrl false) (alt false) (shift false)),
+// or nil when the optional timeout in milliseconds passes without a key
+pub fn fn_read_key(arguments: &[Node]) -> Result<Node, String> {
+    let timeout = match arguments {
+        [] => -1,
+        [Node::Number(ms)] => i32::try_from(*ms).map_err(|_| format!("Invalid timeout: {ms}"))?,
+        _ => return invalid_arguments!("read-key", arguments, ["[]", "[Number(timeout-ms)]"]),
+    };
+    if !wait_for_input(timeout)? {
+        return Ok(Node::List(vec![]));
+    }
+    let Some(first) = read_byte()? else {
+        return Ok(Node::List(vec![]));
+    };
+
+    let mut bytes = vec![first];
+    if first == 0x1b {
+        // The rest of an escape sequence arrives immediately; a lone escape
+        // key press is followed by nothing
+        while wait_for_input(25)? {
+            let Some(byte) = read_byte()? else { break };
+            bytes.push(byte);
+            let sequence_started = bytes.len() > 2 && matches!(bytes[1], b'[' | b'O');
+            if bytes.len() == 2 && !matches!(byte, b'[' | b'O')
+                || sequence_started && (0x40..=0x7e).contains(&byte)
+            {
+                break;
+            }
+        }
+    } else if first >= 0xc0 {
+        let width = if first >= 0xf0 {
+            4
+        } else if first >= 0xe0 {
+            3
+        } else {
+            2
+        };
+        while bytes.len() < width {
+            match read_byte()? {
+                Some(byte) => bytes.push(byte),
+                None => break,
+            }
+        }
+    }
+    Ok(parse_key(&bytes).into_node())
+}
+
+fn with_screen<T>(f: impl FnOnce(&mut Screen) -> T) -> T {
+    STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        let screen = state.screen.get_or_insert_with(|| {
+            // COLUMNS and LINES can claim any size, so keep within the same
+            // bound as screen-resize
+            let (width, height) = terminal_size();
+            let width = width.min(MAX_SCREEN_CELLS);
+            let height = height.min(MAX_SCREEN_CELLS / width.max(1));
+            Screen::new(width, height)
+        });
+        f(screen)
+    })
+}
+
+fn style(foreground: Option<&Node>, background: Option<&Node>) -> Result<String, String> {
+    let mut style = String::new();
+    if let Some(color) = foreground {
+        style.push_str(&color_code(color, false)?);
+    }
+    if let Some(color) = background {
+        style.push_str(&color_code(color, true)?);
+    }
+    Ok(style)
+}
+
+// Sets the size of the screen buffer, which otherwise matches the terminal,
+// and clears it
+//- (test "screen-resize" (begin (screen-resize 4 2) (screen-line 1)) "    ")
+pub fn fn_screen_resize(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Number(width), Node::Number(height)] => {
+            let width = usize::try_from(*width).map_err(|_| format!("Invalid width: {width}"))?;
+            let height =
+                usize::try_from(*height).map_err(|_| format!("Invalid height: {height}"))?;
+            if width
+                .checked_mul(height)
+                .is_none_or(|cells| cells > MAX_SCREEN_CELLS)
+            {
+                return Err(format!(
+                    "Screen of {width}x{height} is larger than {MAX_SCREEN_CELLS} cells"
+                ));
+            }
+            STATE.with(|state| state.borrow_mut().screen = Some(Screen::new(width, height)));
+            Ok(Node::Bool(true))
+        }
+        _ => invalid_arguments!(
+            "screen-resize",
+            arguments,
+            ["[Number(width), Number(height)]"]
+        ),
+    }
+}
+
+//- (test "screen-clear" (begin (screen-resize 3 1) (draw-text 0 0 "abc") (screen-clear) (screen-line 0)) "   ")
+pub fn fn_screen_clear(arguments: &[Node]) -> Result<Node, String> {
+    if !arguments.is_empty() {
+        return invalid_arguments!("screen-clear", arguments, ["[]"]);
+    }
+    with_screen(|screen| screen.back.fill(Cell::blank()));
+    Ok(Node::Bool(true))
+}
+
+// Draws text into the screen buffer at a zero-based column and row, clipped
+// to the screen, optionally with foreground and background colors
+//- (test "draw-text" (begin (screen-resize 6 1) (draw-text 1 0 "hello world" "red") (screen-line 0)) " hello")
+//- (test "draw-text" (begin (screen-resize 3 1) (draw-text -1 0 "abc") (screen-line 0)) "bc ")
+//- (test "draw-text" (begin (screen-resize 3 1) (draw-text 9223372036854775807 0 "ab") (screen-line 0)) "   ")
+pub fn fn_draw_text(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [
+            Node::Number(x),
+            Node::Number(y),
+            Node::Text(text),
+            colors @ ..,
+        ] if colors.len() <= 2 => {
+            let style = style(colors.first(), colors.get(1))?;
+            with_screen(|screen| {
+                for (offset, ch) in (0..).zip(text.chars()) {
+                    let Some(column) = x.checked_add(offset) else {
+                        break;
+                    };
+                    if usize::try_from(column).is_ok_and(|column| column >= screen.width) {
+                        break;
+                    }
+                    screen.put(column, *y, ch, &style);
+                }
+            });
+            Ok(Node::Bool(true))
+        }
+        _ => invalid_arguments!(
+            "draw-text",
+            arguments,
+            [
+                "[Number(x), Number(y), Text(text)]",
+                "[Number(x), Number(y), Text(text), Text(fg)]",
+                "[Number(x), Number(y), Text(text), Text(fg), Text(bg)]"
+            ]
+        ),
+    }
+}
+
+//- (test "draw-box" (begin (screen-resize 4 3) (draw-box 0 0 4 3) (list (screen-line 0) (screen-line 1) (screen-line 2))) (quote ("┌──┐" "│  │" "└──┘")))
+//- (test "draw-box" (begin (screen-resize 6 2) (draw-box 0 0 6 2 "ab") (screen-line 0)) "┌ ab ┐")
+//- (test "draw-box" (begin (screen-resize 3 2) (draw-box 1 0 1000000000000 2) (screen-line 1)) " └─")
+pub fn fn_draw_box(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [
+            Node::Number(x),
+            Node::Number(y),
+            Node::Number(width),
+            Node::Number(height),
+            title @ ..,
+        ] if *width >= 2 && *height >= 2 && title.len() <= 1 => {
+            let (Some(right), Some(bottom)) = (x.checked_add(width - 1), y.checked_add(height - 1))
+            else {
+                return Err(format!(
+                    "Box at {x},{y} of {width}x{height} is out of range"
+                ));
+            };
+            with_screen(|screen| {
+                for column in Screen::clip(x + 1, right, screen.width) {
+                    screen.put(column, *y, '─', "");
+                    screen.put(column, bottom, '─', "");
+                }
+                for row in Screen::clip(y + 1, bottom, screen.height) {
+                    screen.put(*x, row, '│', "");
+                    screen.put(right, row, '│', "");
+                }
+                screen.put(*x, *y, '┌', "");
+                screen.put(right, *y, '┐', "");
+                screen.put(*x, bottom, '└', "");
+                screen.put(right, bottom, '┘', "");
+                if let Some(Node::Text(title)) = title.first() {
+                    let title = format!(" {title} ");
+                    for (offset, ch) in (1..width - 1).zip(title.chars()) {
+                        screen.put(x + offset, *y, ch, "");
+                    }
+                }
+            });
+            Ok(Node::Bool(true))
+        }
+        _ => invalid_arguments!(
+            "draw-box",
+            arguments,
+            [
+                "[Number(x), Number(y), Number(width >= 2), Number(height >= 2)]",
+                "[Number(x), Number(y), Number(width >= 2), Number(height >= 2), Text(title)]"
+            ]
+        ),
+    }
+}
+
+//- (test "screen-line" (begin (screen-resize 2 1) (screen-line 0)) "  ")
+pub fn fn_screen_line(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Number(y)] => with_screen(|screen| {
+            let y = usize::try_from(*y)
+                .ok()
+                .filter(|y| *y < screen.height)
+                .ok_or_else(|| format!("Row out of range: {y}"))?;
+            Ok(Node::Text(
+                screen.back[y * screen.width..(y + 1) * screen.width]
+                    .iter()
+                    .map(|cell| cell.ch)
+                    .collect(),
+            ))
+        }),
+        _ => invalid_arguments!("screen-line", arguments, ["[Number(row)]"]),
+    }
+}
+
+// Writes the cells that changed since the previous call to the terminal
+pub fn fn_screen_present(arguments: &[Node]) -> Result<Node, String> {
+    if !arguments.is_empty() {
+        return invalid_arguments!("screen-present", arguments, ["[]"]);
+    }
+    print!("{}", with_screen(Screen::present));
+    flush()?;
+    Ok(Node::Bool(true))
+}
@@ -1,8 +1,89 @@
+use crate::environment::Environment;
+use crate::eval::eval;
 use crate::invalid_arguments;
+use crate::list::make_record;
 use crate::node::Node;
+use crate::stats;
+use chrono::{
+    DateTime, Datelike, FixedOffset, Months, NaiveDate, NaiveDateTime, Offset, TimeZone, Timelike,
+};
+
+static CLOCK_START: std::sync::OnceLock<std::time::Instant> = std::sync::OnceLock::new();
+
+const DURATION_UNITS: [(&str, i64); 5] = [
+    ("w", 604_800),
+    ("d", 86_400),
+    ("h", 3_600),
+    ("m", 60),
+    ("s", 1),
+];
+
+/// A `Node::Time` is a UTC instant (seconds since the epoch) together with the
+/// offset east of UTC, in seconds, that it is presented in.
+pub fn to_datetime(seconds: i64, offset: i32) -> Result<DateTime<FixedOffset>, String> {
+    let zone = FixedOffset::east_opt(offset).ok_or_else(|| format!("Invalid offset: {offset}"))?;
+    zone.timestamp_opt(seconds, 0)
+        .single()
+        .ok_or_else(|| format!("Invalid timestamp: {seconds}"))
+}
+
+/// A `Node::Time` for the given wall-clock date and time in `offset`.
+pub fn from_local(local: &NaiveDateTime, offset: i32) -> Result<Node, String> {
+    let zone = FixedOffset::east_opt(offset).ok_or_else(|| format!("Invalid offset: {offset}"))?;
+    zone.from_local_datetime(local)
+        .single()
+        .map(|datetime| from_datetime(&datetime))
+        .ok_or_else(|| format!("Invalid local time: {local}"))
+}
+
+pub fn from_datetime(datetime: &DateTime<FixedOffset>) -> Node {
+    Node::Time(datetime.timestamp(), datetime.offset().local_minus_utc())
+}
+
+fn parse_offset_text(text: &str) -> Option<i32> {
+    if matches!(text, "Z" | "UTC" | "GMT") {
+        return Some(0);
+    }
+    let (sign, rest) = match text.as_bytes().first()? {
+        b'+' => (1, &text[1..]),
+        b'-' => (-1, &text[1..]),
+        _ => return None,
+    };
+    let digits = rest.replace(':', "");
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let (hours, minutes) = match digits.len() {
+        1 | 2 => (digits.parse::<i32>().ok()?, 0),
+        4 => (
+            digits[..2].parse::<i32>().ok()?,
+            digits[2..].parse::<i32>().ok()?,
+        ),
+        _ => return None,
+    };
+    (hours <= 23 && minutes <= 59).then_some(sign * (hours * 3600 + minutes * 60))
+}
+
+/// Offsets are given in hours (whole or fractional, e.g. 5.5) or as text like
+/// "+05:30" or "-0330".
+#[allow(clippy::cast_possible_truncation)]
+fn parse_offset(node: &Node) -> Result<i32, String> {
+    let offset = match node {
+        Node::Number(hours) => i32::try_from(*hours).ok().and_then(|h| h.checked_mul(3600)),
+        Node::Float(hours) if hours.is_finite() => Some((hours * 3600.0).round() as i32),
+        Node::Text(text) => parse_offset_text(text),
+        _ => None,
+    };
+    offset
+        .filter(|offset| offset.abs() < 86_400 && offset % 60 == 0)
+        .ok_or_else(|| format!("Invalid offset: {node}"))
+}
 
 //- (test "time" (time->string (time 2025 1 1 12 0 0 -5)) "2025-01-01 12:00:00 UTC-05:00")
 //- (test "time" (time->number (time 1970 1 1 0 0 0 0)) 0)
+//- (test "time" (time->number (time 1970 1 1 1 0 0 1)) 0)
+//- (test "time" (time->string (time 2025 1 1 12 0 0 5.5)) "2025-01-01 12:00:00 UTC+05:30")
+//- (test "time" (time->string (time 2025 1 1 12 0 0 "-03:30")) "2025-01-01 12:00:00 UTC-03:30")
 pub fn fn_time(arguments: &[Node]) -> Result<Node, String> {
     match arguments {
         [Node::Number(year), Node::Number(month), Node::Number(day)] => {
@@ -48,7 +129,7 @@ pub fn fn_time(arguments: &[Node]) -> Result<Node, String> {
             Node::Number(hour),
             Node::Number(minute),
             Node::Number(second),
-            Node::Number(offset),
+            offset,
         ] => {
             let year = i32::try_from(*year).map_err(|_| "Invalid year")?;
             let month = u32::try_from(*month).map_err(|_| "Invalid month")?;
@@ -56,7 +137,7 @@ pub fn fn_time(arguments: &[Node]) -> Result<Node, String> {
             let hour = u32::try_from(*hour).map_err(|_| "Invalid hour")?;
             let minute = u32::try_from(*minute).map_err(|_| "Invalid minute")?;
             let second = u32::try_from(*second).map_err(|_| "Invalid second")?;
-            let offset = i32::try_from(*offset).map_err(|_| "Invalid offset")?;
+            let offset = parse_offset(offset)?;
 
             let Some(dt) = chrono::NaiveDate::from_ymd_opt(year, month, day) else {
                 return Err("Invalid date".to_string());
@@ -64,7 +145,7 @@ pub fn fn_time(arguments: &[Node]) -> Result<Node, String> {
             let Some(dt_seconds) = dt.and_hms_opt(hour, minute, second) else {
                 return Err("Invalid time".to_string());
             };
-            Ok(Node::Time(dt_seconds.and_utc().timestamp(), offset * 3600))
+            from_local(&dt_seconds, offset)
         }
         _ => invalid_arguments!(
             "time",
@@ -83,21 +164,28 @@ pub fn fn_now(arguments: &[Node]) -> Result<Node, String> {
         [] => {
             let now = chrono::Local::now();
             let offset = now.offset().local_minus_utc();
-            let seconds = now.timestamp() + i64::from(offset);
-            Ok(Node::Time(seconds, offset))
+            Ok(Node::Time(now.timestamp(), offset))
         }
         _ => invalid_arguments!("now", arguments, ["[]"]),
     }
 }
 
+/// A time moved by a duration, as long as it is still one that can be shown.
+fn shifted_time(seconds: Option<i64>, offset: i32) -> Result<Node, String> {
+    let seconds = seconds.ok_or_else(|| "Time out of range".to_string())?;
+    to_datetime(seconds, offset).map_err(|_| format!("Time out of range: {seconds}"))?;
+    Ok(Node::Time(seconds, offset))
+}
+
 //- (test "add-days" (add-days 1 (time 2025 1 1 12 0 0 -5)) (time 2025 1 2 12 0 0 -5))
 //- (test "add-days" (add-days -1 (time 2025 1 1 12 0 0 -5)) (time 2024 12 31 12 0 0 -5))
 pub fn fn_add_days(arguments: &[Node]) -> Result<Node, String> {
     match arguments {
-        [Node::Number(days), Node::Time(seconds, offset)] => {
-            let new_seconds = *seconds + days * 86400;
-            Ok(Node::Time(new_seconds, *offset))
-        }
+        [Node::Number(days), Node::Time(seconds, offset)] => shifted_time(
+            days.checked_mul(86400)
+                .and_then(|delta| seconds.checked_add(delta)),
+            *offset,
+        ),
         _ => invalid_arguments!(
             "add-days",
             arguments,
@@ -110,10 +198,12 @@ pub fn fn_add_days(arguments: &[Node]) -> Result<Node, String> {
 //- (test "add-hours" (add-hours -1 (time 2025 1 1 12 0 0 -5)) (time 2025 1 1 11 0 0 -5))
 pub fn fn_add_hours(arguments: &[Node]) -> Result<Node, String> {
     match arguments {
-        [Node::Number(hours), Node::Time(seconds, offset)] => {
-            let new_seconds = *seconds + hours * 3600;
-            Ok(Node::Time(new_seconds, *offset))
-        }
+        [Node::Number(hours), Node::Time(seconds, offset)] => shifted_time(
+            hours
+                .checked_mul(3600)
+                .and_then(|delta| seconds.checked_add(delta)),
+            *offset,
+        ),
         _ => invalid_arguments!(
             "add-hours",
             arguments,
@@ -126,10 +216,12 @@ pub fn fn_add_hours(arguments: &[Node]) -> Result<Node, String> {
 //- (test "add-minutes" (add-minutes -1 (time 2025 1 1 12 0 0 -5)) (time 2025 1 1 11 59 0 -5))
 pub fn fn_add_minutes(arguments: &[Node]) -> Result<Node, String> {
     match arguments {
-        [Node::Number(minutes), Node::Time(seconds, offset)] => {
-            let new_seconds = *seconds + minutes * 60;
-            Ok(Node::Time(new_seconds, *offset))
-        }
+        [Node::Number(minutes), Node::Time(seconds, offset)] => shifted_time(
+            minutes
+                .checked_mul(60)
+                .and_then(|delta| seconds.checked_add(delta)),
+            *offset,
+        ),
         _ => invalid_arguments!(
             "add-minutes",
             arguments,
@@ -143,8 +235,7 @@ pub fn fn_add_minutes(arguments: &[Node]) -> Result<Node, String> {
 pub fn fn_add_seconds(arguments: &[Node]) -> Result<Node, String> {
     match arguments {
         [Node::Number(seconds), Node::Time(current_seconds, offset)] => {
-            let new_seconds = *current_seconds + seconds;
-            Ok(Node::Time(new_seconds, *offset))
+            shifted_time(current_seconds.checked_add(*seconds), *offset)
         }
         _ => invalid_arguments!(
             "add-seconds",
@@ -153,3 +244,465 @@ pub fn fn_add_seconds(arguments: &[Node]) -> Result<Node, String> {
         ),
     }
 }
+
+fn parse_with_pattern(pattern: &str, text: &str) -> Result<DateTime<FixedOffset>, String> {
+    if let Ok(datetime) = DateTime::parse_from_str(text, pattern) {
+        return Ok(datetime);
+    }
+    let utc = FixedOffset::east_opt(0).expect("zero offset is valid");
+    if let Ok(datetime) = NaiveDateTime::parse_from_str(text, pattern) {
+        return Ok(datetime.and_utc().with_timezone(&utc));
+    }
+    match NaiveDate::parse_from_str(text, pattern) {
+        Ok(date) => Ok(date
+            .and_time(chrono::NaiveTime::MIN)
+            .and_utc()
+            .with_timezone(&utc)),
+        Err(e) => Err(format!(
+            "Failed to parse time '{text}' with pattern '{pattern}': {e}"
+        )),
+    }
+}
+
+//- (test "parse-time" (parse-time "2025-01-01T12:00:00-05:00") (time 2025 1 1 12 0 0 -5))
+//- (test "parse-time" (parse-time "Wed, 01 Jan 2025 12:00:00 +0000") (time 2025 1 1 12 0 0))
+//- (test "parse-time" (parse-time "%d/%m/%Y %H:%M" "31/12/2024 23:59") (time 2024 12 31 23 59 0))
+//- (test "parse-time" (parse-time "%Y-%m-%d" "2024-02-29") (time 2024 2 29))
+pub fn fn_parse_time(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(text)] => DateTime::parse_from_rfc3339(text)
+            .or_else(|_| DateTime::parse_from_rfc2822(text))
+            .map(|datetime| from_datetime(&datetime))
+            .map_err(|_| format!("Failed to parse time '{text}' as RFC 3339 or RFC 2822")),
+        [Node::Text(pattern), Node::Text(text)] => {
+            parse_with_pattern(pattern, text).map(|datetime| from_datetime(&datetime))
+        }
+        _ => invalid_arguments!(
+            "parse-time",
+            arguments,
+            ["[Text(text)]", "[Text(pattern), Text(text)]"]
+        ),
+    }
+}
+
+//- (test "format-time" (format-time "%d.%m.%Y %H:%M" (time 2025 3 7 8 5 0)) "07.03.2025 08:05")
+//- (test "format-time" (format-time "%A %z" (time 2025 1 1 12 0 0 -5)) "Wednesday -0500")
+pub fn fn_format_time(arguments: &[Node]) -> Result<Node, String> {
+    use std::fmt::Write;
+
+    match arguments {
+        [Node::Text(pattern), Node::Time(seconds, offset)] => {
+            let datetime = to_datetime(*seconds, *offset)?;
+            let mut text = String::new();
+            write!(text, "{}", datetime.format(pattern))
+                .map_err(|_| format!("Invalid time format pattern: {pattern}"))?;
+            Ok(Node::Text(text))
+        }
+        _ => invalid_arguments!(
+            "format-time",
+            arguments,
+            ["[Text(pattern), Time(seconds, offset)]"]
+        ),
+    }
+}
+
+fn time_field(
+    name: &str,
+    arguments: &[Node],
+    field: impl Fn(&DateTime<FixedOffset>) -> u32,
+) -> Result<Node, String> {
+    match arguments {
+        [Node::Time(seconds, offset)] => Ok(Node::Number(i64::from(field(&to_datetime(
+            *seconds, *offset,
+        )?)))),
+        _ => invalid_arguments!(name, arguments, ["[Time(seconds, offset)]"]),
+    }
+}
+
+//- (test "time-year" (time-year (time 2025 1 1 12 0 0 -5)) 2025)
+pub fn fn_time_year(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Time(seconds, offset)] => Ok(Node::Number(i64::from(
+            to_datetime(*seconds, *offset)?.year(),
+        ))),
+        _ => invalid_arguments!("time-year", arguments, ["[Time(seconds, offset)]"]),
+    }
+}
+
+//- (test "time-month" (time-month (time 2025 7 1)) 7)
+pub fn fn_time_month(arguments: &[Node]) -> Result<Node, String> {
+    time_field("time-month", arguments, Datelike::month)
+}
+
+//- (test "time-day" (time-day (time 2025 7 31)) 31)
+pub fn fn_time_day(arguments: &[Node]) -> Result<Node, String> {
+    time_field("time-day", arguments, Datelike::day)
+}
+
+//- (test "time-hour" (time-hour (time 2025 1 1 23 0 0 -5)) 23)
+pub fn fn_time_hour(arguments: &[Node]) -> Result<Node, String> {
+    time_field("time-hour", arguments, Timelike::hour)
+}
+
+//- (test "time-minute" (time-minute (time 2025 1 1 23 45 0)) 45)
+pub fn fn_time_minute(arguments: &[Node]) -> Result<Node, String> {
+    time_field("time-minute", arguments, Timelike::minute)
+}
+
+//- (test "time-second" (time-second (time 2025 1 1 23 45 10)) 10)
+pub fn fn_time_second(arguments: &[Node]) -> Result<Node, String> {
+    time_field("time-second", arguments, Timelike::second)
+}
+
+// Weekdays are numbered 1 (Monday) through 7 (Sunday), as in ISO 8601
+//- (test "time-weekday" (time-weekday (time 2025 1 1)) 3)
+//- (test "time-weekday" (time-weekday (time 2025 1 5)) 7)
+pub fn fn_time_weekday(arguments: &[Node]) -> Result<Node, String> {
+    time_field("time-weekday", arguments, |datetime| {
+        datetime.weekday().number_from_monday()
+    })
+}
+
+//- (test "time-day-of-year" (time-day-of-year (time 2024 12 31)) 366)
+pub fn fn_time_day_of_year(arguments: &[Node]) -> Result<Node, String> {
+    time_field("time-day-of-year", arguments, Datelike::ordinal)
+}
+
+//- (test "time-iso-week" (time-iso-week (time 2025 1 1)) 1)
+//- (test "time-iso-week" (time-iso-week (time 2021 1 3)) 53)
+pub fn fn_time_iso_week(arguments: &[Node]) -> Result<Node, String> {
+    time_field("time-iso-week", arguments, |datetime| {
+        datetime.iso_week().week()
+    })
+}
+
+/// Human-readable form of a duration, e.g. "1d 3h 20m" or "-45s".
+pub fn format_duration(seconds: i64) -> String {
+    if seconds == 0 {
+        return "0s".to_string();
+    }
+    let mut remaining = seconds.unsigned_abs();
+    let mut parts = vec![];
+    for (unit, size) in &DURATION_UNITS[1..] {
+        let size = size.unsigned_abs();
+        if remaining >= size {
+            parts.push(format!("{}{unit}", remaining / size));
+            remaining %= size;
+        }
+    }
+    let sign = if seconds < 0 { "-" } else { "" };
+    format!("{sign}{}", parts.join(" "))
+}
+
+fn parse_duration(text: &str) -> Result<i64, String> {
+    let invalid = || format!("Invalid duration: '{text}' (expected e.g. \"1h30m\" or \"2d 4h\")");
+    let trimmed = text.trim();
+    let (sign, mut rest) = match trimmed.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, trimmed),
+    };
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+    let mut seconds: i64 = 0;
+    while !rest.is_empty() {
+        rest = rest.trim_start();
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .ok_or_else(invalid)?;
+        let amount = rest[..digits].parse::<i64>().map_err(|_| invalid())?;
+        rest = &rest[digits..];
+        let (unit, size) = DURATION_UNITS
+            .iter()
+            .find(|(unit, _)| rest.starts_with(unit))
+            .ok_or_else(invalid)?;
+        rest = &rest[unit.len()..];
+        seconds = amount
+            .checked_mul(*size)
+            .and_then(|amount| seconds.checked_add(amount))
+            .ok_or_else(|| format!("Duration too large: '{text}'"))?;
+    }
+    Ok(sign * seconds)
+}
+
+fn duration_of(name: &str, arguments: &[Node], size: i64) -> Result<Node, String> {
+    match arguments {
+        [Node::Number(amount)] => amount
+            .checked_mul(size)
+            .map(Node::Duration)
+            .ok_or_else(|| format!("Duration too large: {amount}")),
+        _ => invalid_arguments!(name, arguments, ["[Number(amount)]"]),
+    }
+}
+
+//- (test "seconds" (seconds 90) (duration "1m30s"))
+pub fn fn_seconds(arguments: &[Node]) -> Result<Node, String> {
+    duration_of("seconds", arguments, 1)
+}
+
+//- (test "minutes" (minutes 90) (duration "1h30m"))
+pub fn fn_minutes(arguments: &[Node]) -> Result<Node, String> {
+    duration_of("minutes", arguments, 60)
+}
+
+//- (test "hours" (hours 36) (duration "1d 12h"))
+pub fn fn_hours(arguments: &[Node]) -> Result<Node, String> {
+    duration_of("hours", arguments, 3600)
+}
+
+//- (test "days" (days 14) (duration "2w"))
+pub fn fn_days(arguments: &[Node]) -> Result<Node, String> {
+    duration_of("days", arguments, 86400)
+}
+
+//- (test "duration" (duration "1h30m") (seconds 5400))
+//- (test "duration" (duration "-2m 5s") (seconds -125))
+//- (test "duration" (duration 42) (seconds 42))
+pub fn fn_duration(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(text)] => parse_duration(text).map(Node::Duration),
+        [Node::Number(seconds)] => Ok(Node::Duration(*seconds)),
+        _ => invalid_arguments!(
+            "duration",
+            arguments,
+            ["[Text(duration)]", "[Number(seconds)]"]
+        ),
+    }
+}
+
+//- (test "duration->seconds" (duration->seconds (duration "1h")) 3600)
+pub fn fn_duration_to_seconds(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Duration(seconds)] => Ok(Node::Number(*seconds)),
+        _ => invalid_arguments!("duration->seconds", arguments, ["[Duration(seconds)]"]),
+    }
+}
+
+// The duration from `start` to `end`; negative when `end` is earlier
+//- (test "time-diff" (time-diff (time 2025 1 1) (time 2025 1 2 3 20 0)) (duration "1d 3h 20m"))
+//- (test "time-diff" (time-diff (time 2025 1 2) (time 2025 1 1)) (days -1))
+pub fn fn_time_diff(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Time(start, start_offset), Node::Time(end, end_offset)] => {
+            let start = to_datetime(*start, *start_offset)?;
+            let end = to_datetime(*end, *end_offset)?;
+            Ok(Node::Duration((end - start).num_seconds()))
+        }
+        _ => invalid_arguments!(
+            "time-diff",
+            arguments,
+            ["[Time(start, offset), Time(end, offset)]"]
+        ),
+    }
+}
+
+//- (test "time+" (time+ (time 2025 1 1 12 0 0 -5) (duration "1d 1h")) (time 2025 1 2 13 0 0 -5))
+//- (test "time+" (time+ (minutes 30) (hours 1)) (duration "1h30m"))
+pub fn fn_time_add(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Time(seconds, offset), Node::Duration(duration)]
+        | [Node::Duration(duration), Node::Time(seconds, offset)] => {
+            shifted_time(seconds.checked_add(*duration), *offset)
+        }
+        [Node::Duration(a), Node::Duration(b)] => a
+            .checked_add(*b)
+            .map(Node::Duration)
+            .ok_or_else(|| "Duration out of range".to_string()),
+        _ => invalid_arguments!(
+            "time+",
+            arguments,
+            [
+                "[Time(seconds, offset), Duration(duration)]",
+                "[Duration(a), Duration(b)]"
+            ]
+        ),
+    }
+}
+
+//- (test "time-" (time- (time 2025 1 1) (hours 1)) (time 2024 12 31 23 0 0))
+//- (test "time-" (time- (time 2025 1 1 1 0 0) (time 2025 1 1)) (hours 1))
+//- (test "time-" (time- (hours 1) (minutes 90)) (minutes -30))
+pub fn fn_time_subtract(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Time(seconds, offset), Node::Duration(duration)] => {
+            shifted_time(seconds.checked_sub(*duration), *offset)
+        }
+        [Node::Time(_, _), Node::Time(_, _)] => {
+            fn_time_diff(&[arguments[1].clone(), arguments[0].clone()])
+        }
+        [Node::Duration(a), Node::Duration(b)] => a
+            .checked_sub(*b)
+            .map(Node::Duration)
+            .ok_or_else(|| "Duration out of range".to_string()),
+        _ => invalid_arguments!(
+            "time-",
+            arguments,
+            [
+                "[Time(seconds, offset), Duration(duration)]",
+                "[Time(a, offset), Time(b, offset)]",
+                "[Duration(a), Duration(b)]"
+            ]
+        ),
+    }
+}
+
+fn add_months(name: &str, arguments: &[Node], months_per_unit: i64) -> Result<Node, String> {
+    match arguments {
+        [Node::Number(amount), Node::Time(seconds, offset)] => {
+            let local = to_datetime(*seconds, *offset)?.naive_local();
+            let months = amount
+                .checked_mul(months_per_unit)
+                .and_then(|months| u32::try_from(months.unsigned_abs()).ok())
+                .map(Months::new)
+                .ok_or_else(|| format!("Too many months to add: {amount}"))?;
+            let shifted = if *amount < 0 {
+                local.checked_sub_months(months)
+            } else {
+                local.checked_add_months(months)
+            }
+            .ok_or_else(|| "Time out of range".to_string())?;
+            from_local(&shifted, *offset)
+        }
+        _ => invalid_arguments!(name, arguments, ["[Number(amount), Time(seconds, offset)]"]),
+    }
+}
+
+// Days past the end of the target month are clamped to its last day
+//- (test "add-months" (add-months 1 (time 2025 1 31 12 0 0 -5)) (time 2025 2 28 12 0 0 -5))
+//- (test "add-months" (add-months -2 (time 2024 4 30)) (time 2024 2 29))
+//- (test "add-months" (add-months 13 (time 2024 1 15)) (time 2025 2 15))
+pub fn fn_add_months(arguments: &[Node]) -> Result<Node, String> {
+    add_months("add-months", arguments, 1)
+}
+
+//- (test "add-years" (add-years 1 (time 2024 2 29)) (time 2025 2 28))
+//- (test "add-years" (add-years -10 (time 2025 6 1)) (time 2015 6 1))
+pub fn fn_add_years(arguments: &[Node]) -> Result<Node, String> {
+    add_months("add-years", arguments, 12)
+}
+
+fn to_zone(zone: &str, seconds: i64) -> Result<i32, String> {
+    if let Some(offset) = parse_offset_text(zone) {
+        return Ok(offset);
+    }
+    let tz = zone
+        .parse::<chrono_tz::Tz>()
+        .map_err(|_| format!("Unknown time zone: {zone}"))?;
+    tz.timestamp_opt(seconds, 0)
+        .single()
+        .map(|datetime| datetime.offset().fix().local_minus_utc())
+        .ok_or_else(|| format!("Invalid timestamp: {seconds}"))
+}
+
+// Named zones resolve to the offset in effect at that instant, so daylight
+// saving time is taken into account
+//- (test "to-timezone" (time->string (to-timezone "Europe/Berlin" (time 2025 1 15 12 0 0))) "2025-01-15 13:00:00 UTC+01:00")
+//- (test "to-timezone" (time->string (to-timezone "Europe/Berlin" (time 2025 7 15 12 0 0))) "2025-07-15 14:00:00 UTC+02:00")
+//- (test "to-timezone" (time->string (to-timezone "Asia/Kolkata" (time 2025 1 1))) "2025-01-01 05:30:00 UTC+05:30")
+//- (test "to-timezone" (to-timezone "UTC" (time 2025 1 1 12 0 0 -5)) (time 2025 1 1 17 0 0))
+pub fn fn_to_timezone(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [Node::Text(zone), Node::Time(seconds, _)] => {
+            Ok(Node::Time(*seconds, to_zone(zone, *seconds)?))
+        }
+        _ => invalid_arguments!(
+            "to-timezone",
+            arguments,
+            ["[Text(zone), Time(seconds, offset)]"]
+        ),
+    }
+}
+
+//- (test "sleep" (sleep 1) true)
+//- (test "sleep" (let ((start (monotonic-ns))) (begin (sleep (seconds 0)) (>= (monotonic-ns) start))) true)
+pub fn fn_sleep(arguments: &[Node]) -> Result<Node, String> {
+    let duration = match arguments {
+        [Node::Number(ms)] => u64::try_from(*ms)
+            .map(std::time::Duration::from_millis)
+            .map_err(|_| format!("Cannot sleep for a negative time: {ms}"))?,
+        [Node::Duration(seconds)] => u64::try_from(*seconds)
+            .map(std::time::Duration::from_secs)
+            .map_err(|_| format!("Cannot sleep for a negative duration: {seconds}"))?,
+        _ => {
+            return invalid_arguments!(
+                "sleep",
+                arguments,
+                ["[Number(milliseconds)]", "[Duration(duration)]"]
+            );
+        }
+    };
+    std::thread::sleep(duration);
+    Ok(Node::Bool(true))
+}
+
+// Nanoseconds elapsed on a monotonic clock; only differences are meaningful
+//- (test "monotonic-ns" (type? (monotonic-ns)) "number")
+pub fn fn_monotonic_ns(arguments: &[Node]) -> Result<Node, String> {
+    match arguments {
+        [] => {
+            let start = CLOCK_START.get_or_init(std::time::Instant::now);
+            i64::try_from(start.elapsed().as_nanos())
+                .map(Node::Number)
+                .map_err(|_| "Monotonic clock overflowed".to_string())
+        }
+        _ => invalid_arguments!("monotonic-ns", arguments, ["[]"]),
+    }
+}
+
+// (bench [iterations [warmup]] expression) evaluates the expression `warmup`
+// times untimed, then `iterations` times, reporting timings in milliseconds
+//- (test "bench" (get (quote iterations) (bench 5 (+ 1 2))) 5)
+//- (test "bench" (get (quote warmup) (bench 20 (+ 1 2))) 2)
+//- (test "bench" (map car (bench 3 0 (+ 1 2))) (quote (iterations warmup min-ms mean-ms median-ms max-ms stddev-ms)))
+#[allow(clippy::cast_precision_loss)]
+pub fn eval_bench(rest: &[Node], env: &mut Environment) -> Result<Node, String> {
+    let count = |node: &Node, env: &mut Environment| match eval(node, env)? {
+        Node::Number(n) if n >= 0 => usize::try_from(n).map_err(|_| format!("Invalid count: {n}")),
+        other => Err(format!("Expected a non-negative count, found {other:?}")),
+    };
+    let (iterations, warmup, expression) = match rest {
+        [expression] => (100, 10, expression),
+        [iterations, expression] => {
+            let iterations = count(iterations, env)?;
+            (iterations, iterations / 10, expression)
+        }
+        [iterations, warmup, expression] => {
+            (count(iterations, env)?, count(warmup, env)?, expression)
+        }
+        _ => {
+            return invalid_arguments!(
+                "bench",
+                rest,
+                [
+                    "[Any(expression)]",
+                    "[Number(iterations), Any(expression)]",
+                    "[Number(iterations), Number(warmup), Any(expression)]"
+                ]
+            );
+        }
+    };
+    if iterations == 0 {
+        return Err("bench needs at least one iteration".to_string());
+    }
+
+    for _ in 0..warmup {
+        eval(expression, env)?;
+    }
+    let mut timings = Vec::new();
+    for _ in 0..iterations {
+        let start = std::time::Instant::now();
+        eval(expression, env)?;
+        timings.push(start.elapsed().as_secs_f64() * 1000.0);
+    }
+
+    let timings = stats::sorted(timings);
+    Ok(make_record(vec![
+        ("iterations", Node::Number(iterations as i64)),
+        ("warmup", Node::Number(warmup as i64)),
+        ("min-ms", Node::Float(timings[0])),
+        ("mean-ms", Node::Float(stats::mean(&timings))),
+        ("median-ms", Node::Float(stats::percentile(&timings, 50.0))),
+        ("max-ms", Node::Float(timings[timings.len() - 1])),
+        ("stddev-ms", Node::Float(stats::variance(&timings).sqrt())),
+    ]))
+}